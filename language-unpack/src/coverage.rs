@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use clap::ValueEnum;
+
+use crate::language::Language;
+use crate::language_file::LanguageFile;
+use crate::texts::TextTree;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CoverageFormat {
+    /// A file × language matrix.
+    Terminal,
+    /// A `file,language,present,rows,empty,missing,unique` row per file and language.
+    Csv,
+}
+
+/// Row counts for one language's copy of a .msg file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub present: bool,
+    pub rows: usize,
+    /// Rows with an empty `text_`.
+    pub empty: usize,
+    /// Keys in the `en` copy of the file that this copy doesn't have.
+    pub missing: usize,
+    /// Keys no other language's copy of the file has.
+    pub unique: usize,
+}
+
+/// Rows are keyed by `id_hash_` and `sub_id_hash_`, the same as `diff`.
+fn keys(language_file: &LanguageFile) -> BTreeSet<(&str, &str)> {
+    language_file
+        .rows_
+        .iter()
+        .map(|row| {
+            (
+                row.column_.id_hash_.as_str(),
+                row.column_.sub_id_hash_.as_str(),
+            )
+        })
+        .collect()
+}
+
+/// Coverage of every .msg file in every language, compared with `en` where the tree has it.
+pub struct CoverageReport {
+    pub languages: Vec<Language>,
+    pub files: BTreeMap<String, BTreeMap<Language, Coverage>>,
+}
+
+impl CoverageReport {
+    /// Reports on `languages`, which may leave out languages in the tree that are only there to
+    /// compare against.
+    pub fn new(tree: &TextTree, languages: &[Language]) -> Self {
+        let mut files = BTreeMap::new();
+
+        for (name, copies) in &tree.files {
+            let copy_keys: BTreeMap<Language, BTreeSet<(&str, &str)>> = copies
+                .iter()
+                .map(|(language, language_file)| (*language, keys(language_file)))
+                .collect();
+            let english = copy_keys.get(&Language::English);
+            let mut coverage = BTreeMap::new();
+
+            for &language in languages {
+                let Some(language_file) = copies.get(&language) else {
+                    coverage.insert(
+                        language,
+                        Coverage {
+                            missing: english.map_or(0, BTreeSet::len),
+                            ..Coverage::default()
+                        },
+                    );
+                    continue;
+                };
+
+                let own_keys = &copy_keys[&language];
+                let unique = own_keys
+                    .iter()
+                    .filter(|key| {
+                        copy_keys
+                            .iter()
+                            .all(|(other, keys)| *other == language || !keys.contains(*key))
+                    })
+                    .count();
+
+                coverage.insert(
+                    language,
+                    Coverage {
+                        present: true,
+                        rows: language_file.rows_.len(),
+                        empty: language_file
+                            .rows_
+                            .iter()
+                            .filter(|row| row.column_.text_.is_empty())
+                            .count(),
+                        missing: english.map_or(0, |english| english.difference(own_keys).count()),
+                        unique: if copy_keys.len() > 1 { unique } else { 0 },
+                    },
+                );
+            }
+
+            files.insert(name.clone(), coverage);
+        }
+
+        CoverageReport {
+            languages: languages.to_vec(),
+            files,
+        }
+    }
+
+    pub fn write_csv(&self, output: impl Write) -> anyhow::Result<()> {
+        let mut writer = csv::Writer::from_writer(output);
+        writer.write_record([
+            "file", "language", "present", "rows", "empty", "missing", "unique",
+        ])?;
+
+        for (name, coverage) in &self.files {
+            for (language, coverage) in coverage {
+                writer.write_record([
+                    name.clone(),
+                    language.to_string(),
+                    coverage.present.to_string(),
+                    coverage.rows.to_string(),
+                    coverage.empty.to_string(),
+                    coverage.missing.to_string(),
+                    coverage.unique.to_string(),
+                ])?;
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// A file × language matrix of `rows/empty/missing/unique`, with `-` for missing files.
+    pub fn to_terminal(&self) -> String {
+        let mut table = vec![];
+        let mut header = vec!["file".to_string()];
+        header.extend(self.languages.iter().map(Language::to_string));
+        table.push(header);
+
+        let mut totals = vec![Coverage::default(); self.languages.len()];
+
+        for (name, coverage) in &self.files {
+            let mut line = vec![name.clone()];
+
+            for (index, language) in self.languages.iter().enumerate() {
+                let coverage = &coverage[language];
+                let total = &mut totals[index];
+                total.rows += coverage.rows;
+                total.empty += coverage.empty;
+                total.missing += coverage.missing;
+                total.unique += coverage.unique;
+
+                line.push(if coverage.present {
+                    cell(coverage)
+                } else {
+                    "-".to_string()
+                });
+            }
+
+            table.push(line);
+        }
+
+        let mut line = vec!["total".to_string()];
+        line.extend(totals.iter().map(cell));
+        table.push(line);
+
+        let widths: Vec<usize> = (0..table[0].len())
+            .map(|column| {
+                table
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut output = String::from("rows/empty/missing/unique, missing is compared with en\n\n");
+
+        for line in &table {
+            let cells: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (text, width))| {
+                    if column == 0 {
+                        format!("{:<width$}", text, width = width)
+                    } else {
+                        format!("{:>width$}", text, width = width)
+                    }
+                })
+                .collect();
+
+            output.push_str(cells.join("  ").trim_end());
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+fn cell(coverage: &Coverage) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        coverage.rows, coverage.empty, coverage.missing, coverage.unique
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language_file::{LanguageRow, LanguageRowColumn};
+
+    #[test]
+    fn counts_empty_missing_and_unique_keys() {
+        let mut copies = BTreeMap::new();
+        copies.insert(
+            Language::English,
+            LanguageFile::from_texts(&[("TXT_A", "A"), ("TXT_B", "B"), ("TXT_C", "")]),
+        );
+        let mut japanese = LanguageFile::from_texts(&[("TXT_A", "エー"), ("TXT_D", "")]);
+        japanese.rows_.push(LanguageRow::new(LanguageRowColumn::new(
+            "TXT_A", "1", "エー",
+        )));
+        copies.insert(Language::Japanese, japanese);
+
+        let mut tree = TextTree {
+            files: BTreeMap::new(),
+        };
+        tree.files.insert("text.msg".to_string(), copies);
+        tree.files.insert(
+            "text_en_only.msg".to_string(),
+            [(
+                Language::English,
+                LanguageFile::from_texts(&[("TXT_E", "E")]),
+            )]
+            .into(),
+        );
+
+        let report = CoverageReport::new(&tree, &[Language::English, Language::Japanese]);
+        let japanese = &report.files["text.msg"][&Language::Japanese];
+        assert_eq!(
+            *japanese,
+            Coverage {
+                present: true,
+                rows: 3,
+                empty: 1,
+                missing: 2,
+                unique: 2,
+            }
+        );
+        assert_eq!(report.files["text.msg"][&Language::English].unique, 2);
+
+        let absent = &report.files["text_en_only.msg"][&Language::Japanese];
+        assert!(!absent.present);
+        assert_eq!(absent.missing, 1);
+
+        let terminal = report.to_terminal();
+        assert!(terminal.contains("text_en_only.msg  1/0/0/0        -\n"));
+        assert!(terminal.contains("total             4/1/0/2  3/1/3/2\n"));
+    }
+}
@@ -0,0 +1,401 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::export::find_msg_files;
+use crate::language_file::LanguageFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    /// Readable diff, coloured when printing to a terminal.
+    Terminal,
+    Json,
+    Markdown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Row {
+    pub id_hash_: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub sub_id_hash_: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModifiedRow {
+    pub id_hash_: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub sub_id_hash_: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Rows of one .msg file that were added, removed or modified, matched by `id_hash_` and
+/// `sub_id_hash_`.
+#[derive(Debug, Default, Serialize)]
+pub struct FileDiff {
+    pub added: Vec<Row>,
+    pub removed: Vec<Row>,
+    pub modified: Vec<ModifiedRow>,
+}
+
+impl FileDiff {
+    /// Rows sharing a key are compared in the order they appear in each file.
+    pub fn new(old: &LanguageFile, new: &LanguageFile) -> Self {
+        let rows = |language_file: &LanguageFile| {
+            let mut rows: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+
+            for row in &language_file.rows_ {
+                let column = &row.column_;
+                rows.entry((column.id_hash_.clone(), column.sub_id_hash_.clone()))
+                    .or_default()
+                    .push(column.text_.clone());
+            }
+
+            rows
+        };
+
+        let old_rows = rows(old);
+        let mut new_rows = rows(new);
+        let mut diff = FileDiff::default();
+
+        for ((id_hash_, sub_id_hash_), old_texts) in old_rows {
+            let new_texts = new_rows
+                .remove(&(id_hash_.clone(), sub_id_hash_.clone()))
+                .unwrap_or_default();
+
+            for (i, old_text) in old_texts.iter().enumerate() {
+                match new_texts.get(i) {
+                    None => diff.removed.push(Row {
+                        id_hash_: id_hash_.clone(),
+                        sub_id_hash_: sub_id_hash_.clone(),
+                        text: old_text.clone(),
+                    }),
+                    Some(new_text) if new_text != old_text => diff.modified.push(ModifiedRow {
+                        id_hash_: id_hash_.clone(),
+                        sub_id_hash_: sub_id_hash_.clone(),
+                        old: old_text.clone(),
+                        new: new_text.clone(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+
+            for new_text in new_texts.into_iter().skip(old_texts.len()) {
+                diff.added.push(Row {
+                    id_hash_: id_hash_.clone(),
+                    sub_id_hash_: sub_id_hash_.clone(),
+                    text: new_text,
+                });
+            }
+        }
+
+        for ((id_hash_, sub_id_hash_), new_texts) in new_rows {
+            for new_text in new_texts {
+                diff.added.push(Row {
+                    id_hash_: id_hash_.clone(),
+                    sub_id_hash_: sub_id_hash_.clone(),
+                    text: new_text,
+                });
+            }
+        }
+
+        // Extra duplicates were added before the new keys, so put them back in key order.
+        diff.added
+            .sort_by(|a, b| (&a.id_hash_, &a.sub_id_hash_).cmp(&(&b.id_hash_, &b.sub_id_hash_)));
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Changed files grouped by language directory, then by file path within it. Single files are
+/// grouped under an empty language.
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct Diff {
+    pub languages: BTreeMap<String, BTreeMap<String, FileDiff>>,
+}
+
+fn open(path: &Path) -> anyhow::Result<LanguageFile> {
+    LanguageFile::open(&path.to_string_lossy()).context(format!(
+        "Could not open language file at path: {}",
+        path.display()
+    ))
+}
+
+impl Diff {
+    /// Diffs two .msg files, or every .msg file in two text directories. Files only in one
+    /// directory have all their rows added or removed.
+    pub fn new(old: &Path, new: &Path) -> anyhow::Result<Self> {
+        let mut diff = Diff::default();
+
+        if !old.is_dir() && !new.is_dir() {
+            let name = new.file_name().unwrap_or_default().to_string_lossy();
+            diff.insert("", &name, FileDiff::new(&open(old)?, &open(new)?));
+            return Ok(diff);
+        }
+
+        let relative = |root: &Path| -> anyhow::Result<Vec<PathBuf>> {
+            Ok(find_msg_files(root)?
+                .into_iter()
+                .filter_map(|path| path.strip_prefix(root).ok().map(Path::to_path_buf))
+                .collect())
+        };
+
+        let mut files = relative(old)?;
+        files.extend(relative(new)?);
+        files.sort();
+        files.dedup();
+
+        let empty = LanguageFile::new(Vec::new());
+
+        for file in files {
+            let (old_path, new_path) = (old.join(&file), new.join(&file));
+            let old_file = if old_path.is_file() {
+                open(&old_path)?
+            } else {
+                empty.clone()
+            };
+            let new_file = if new_path.is_file() {
+                open(&new_path)?
+            } else {
+                empty.clone()
+            };
+
+            // text/{lang}/file.msg is grouped under its language directory.
+            let mut components = file.components();
+            let (language, name) = match (components.next(), components.as_path()) {
+                (Some(Component::Normal(language)), rest) if rest != Path::new("") => {
+                    (language.to_string_lossy().to_string(), rest.to_path_buf())
+                }
+                _ => (String::new(), file.clone()),
+            };
+
+            diff.insert(
+                &language,
+                &name.to_string_lossy(),
+                FileDiff::new(&old_file, &new_file),
+            );
+        }
+
+        Ok(diff)
+    }
+
+    fn insert(&mut self, language: &str, name: &str, file_diff: FileDiff) {
+        if !file_diff.is_empty() {
+            self.languages
+                .entry(language.to_string())
+                .or_default()
+                .insert(name.to_string(), file_diff);
+        }
+    }
+
+    fn files(&self) -> impl Iterator<Item = &FileDiff> {
+        self.languages.values().flat_map(BTreeMap::values)
+    }
+
+    pub fn summary(&self) -> String {
+        let count = |f: fn(&FileDiff) -> usize| self.files().map(f).sum::<usize>();
+
+        format!(
+            "{} added, {} removed, {} modified in {} files",
+            count(|file| file.added.len()),
+            count(|file| file.removed.len()),
+            count(|file| file.modified.len()),
+            self.files().count()
+        )
+    }
+
+    pub fn render(&self, format: DiffFormat) -> anyhow::Result<String> {
+        Ok(match format {
+            DiffFormat::Terminal => self.to_terminal(std::io::stdout().is_terminal()),
+            DiffFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+            DiffFormat::Markdown => self.to_markdown(),
+        })
+    }
+
+    pub fn to_terminal(&self, color: bool) -> String {
+        let paint = |code: &str, text: String| {
+            if color {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text
+            }
+        };
+        let mut output = String::new();
+
+        for (language, files) in &self.languages {
+            if !language.is_empty() {
+                writeln!(output, "{}", paint("1", format!("== {} ==", language))).unwrap();
+            }
+
+            for (name, file) in files {
+                writeln!(output, "{}", paint("1", name.clone())).unwrap();
+
+                for row in &file.removed {
+                    let line = format!(
+                        "- {}: {:?}",
+                        row_name(&row.id_hash_, &row.sub_id_hash_),
+                        row.text
+                    );
+                    writeln!(output, "  {}", paint("31", line)).unwrap();
+                }
+
+                for row in &file.added {
+                    let line = format!(
+                        "+ {}: {:?}",
+                        row_name(&row.id_hash_, &row.sub_id_hash_),
+                        row.text
+                    );
+                    writeln!(output, "  {}", paint("32", line)).unwrap();
+                }
+
+                for row in &file.modified {
+                    let line = format!(
+                        "~ {}: {:?} -> {:?}",
+                        row_name(&row.id_hash_, &row.sub_id_hash_),
+                        row.old,
+                        row.new
+                    );
+                    writeln!(output, "  {}", paint("33", line)).unwrap();
+                }
+            }
+
+            writeln!(output).unwrap();
+        }
+
+        writeln!(output, "{}", self.summary()).unwrap();
+        output
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut output = String::new();
+
+        writeln!(output, "# Text changes").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "{}", self.summary()).unwrap();
+
+        for (language, files) in &self.languages {
+            if !language.is_empty() {
+                writeln!(output).unwrap();
+                writeln!(output, "## {}", language).unwrap();
+            }
+
+            for (name, file) in files {
+                writeln!(output).unwrap();
+                writeln!(output, "### {}", name).unwrap();
+                writeln!(output).unwrap();
+                writeln!(output, "| Change | id_hash_ | Old | New |").unwrap();
+                writeln!(output, "| --- | --- | --- | --- |").unwrap();
+
+                for row in &file.removed {
+                    let id = row_name(&row.id_hash_, &row.sub_id_hash_);
+                    writeln!(
+                        output,
+                        "| Removed | `{}` | {} | |",
+                        id,
+                        markdown_cell(&row.text)
+                    )
+                    .unwrap();
+                }
+
+                for row in &file.added {
+                    let id = row_name(&row.id_hash_, &row.sub_id_hash_);
+                    writeln!(
+                        output,
+                        "| Added | `{}` | | {} |",
+                        id,
+                        markdown_cell(&row.text)
+                    )
+                    .unwrap();
+                }
+
+                for row in &file.modified {
+                    let id = row_name(&row.id_hash_, &row.sub_id_hash_);
+                    writeln!(
+                        output,
+                        "| Modified | `{}` | {} | {} |",
+                        id,
+                        markdown_cell(&row.old),
+                        markdown_cell(&row.new)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        output
+    }
+}
+
+pub fn row_name(id_hash_: &str, sub_id_hash_: &str) -> String {
+    if sub_id_hash_.is_empty() {
+        id_hash_.to_string()
+    } else {
+        format!("{} ({})", id_hash_, sub_id_hash_)
+    }
+}
+
+/// Escapes text so it stays in one Markdown table cell.
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('<', "&lt;")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_added_removed_and_modified_rows() {
+        let old =
+            LanguageFile::from_texts(&[("TXT_A", "same"), ("TXT_B", "old"), ("TXT_C", "gone")]);
+        let new = LanguageFile::from_texts(&[
+            ("TXT_A", "same"),
+            ("TXT_B", "new|text"),
+            ("TXT_D", "added"),
+        ]);
+
+        let file_diff = FileDiff::new(&old, &new);
+        assert_eq!(file_diff.added.len(), 1);
+        assert_eq!(file_diff.removed[0].id_hash_, "TXT_C");
+        assert_eq!(file_diff.modified[0].new, "new|text");
+
+        let mut diff = Diff::default();
+        diff.insert("en", "text.msg", file_diff);
+        diff.insert("jp", "text.msg", FileDiff::new(&old, &old));
+
+        assert_eq!(diff.summary(), "1 added, 1 removed, 1 modified in 1 files");
+        assert!(diff
+            .to_markdown()
+            .contains("| Modified | `TXT_B` | old | new\\|text |"));
+        assert!(diff
+            .to_terminal(false)
+            .contains("== en ==\ntext.msg\n  - TXT_C: \"gone\"\n"));
+        assert!(!diff.languages.contains_key("jp"));
+
+        let old = LanguageFile::from_texts(&[("TXT_A", "first"), ("TXT_A", "second")]);
+        let new = LanguageFile::from_texts(&[
+            ("TXT_A", "first"),
+            ("TXT_A", "changed"),
+            ("TXT_A", "third"),
+        ]);
+
+        let file_diff = FileDiff::new(&old, &new);
+        assert_eq!(file_diff.modified[0].old, "second");
+        assert_eq!(file_diff.added[0].text, "third");
+        assert!(file_diff.removed.is_empty());
+    }
+}
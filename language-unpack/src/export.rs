@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use xxhash32_lib::xxhash32_custom;
+
+use crate::json::ToJson;
+use crate::language_file::LanguageFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// `id,id_hash_,sub_id_hash_,text_` rows.
+    Csv,
+    /// The whole file as JSON, including fields the CSV leaves out.
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Writes every row as `id,id_hash_,sub_id_hash_,text_`, where `id` is the xxhash32 of `id_hash_`.
+pub fn write_csv(language_file: &LanguageFile, output: impl Write) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record(["id", "id_hash_", "sub_id_hash_", "text_"])?;
+
+    for row in &language_file.rows_ {
+        let column = &row.column_;
+        let id = format!("{:08x}", xxhash32_custom(column.id_hash_.as_bytes()));
+
+        writer.write_record([&id, &column.id_hash_, &column.sub_id_hash_, &column.text_])?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes the whole file as JSON, which `pack` turns back into the same .msg file.
+pub fn write_json(language_file: &LanguageFile, mut output: impl Write) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut output, &ToJson(&language_file.to_value()?))?;
+    writeln!(output)?;
+    output.flush()?;
+
+    Ok(())
+}
+
+fn export_file(input: &Path, output: &Path, format: ExportFormat) -> anyhow::Result<()> {
+    let language_file = LanguageFile::open(&input.to_string_lossy()).context(format!(
+        "Could not open language file at path: {}",
+        input.display()
+    ))?;
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let file = File::create(output).context(format!(
+        "Could not create export file at path: {}",
+        output.display()
+    ))?;
+
+    match format {
+        ExportFormat::Csv => write_csv(&language_file, BufWriter::new(file)),
+        ExportFormat::Json => write_json(&language_file, BufWriter::new(file)),
+    }
+}
+
+/// Finds every .msg file under `directory`, sorted so exports are deterministic.
+pub fn find_msg_files(directory: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries = std::fs::read_dir(directory).context(format!(
+        "Could not read directory at path: {}",
+        directory.display()
+    ))?;
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            files.extend(find_msg_files(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "msg") {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Exports a .msg file, or every .msg file under a directory, returning how many were exported.
+///
+/// Directory exports mirror the input tree under `output`, or write next to each .msg file.
+pub fn export_path(
+    input: &Path,
+    output: Option<&Path>,
+    format: ExportFormat,
+) -> anyhow::Result<usize> {
+    if !input.is_dir() {
+        let output = output
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| input.with_extension(format.extension()));
+
+        export_file(input, &output, format)?;
+        return Ok(1);
+    }
+
+    let files = find_msg_files(input)?;
+
+    for file in &files {
+        let relative = file.strip_prefix(input)?.with_extension(format.extension());
+        let output = match output {
+            Some(output) => output.join(relative),
+            None => input.join(relative),
+        };
+
+        export_file(file, &output, format)?;
+    }
+
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_quoted_csv() {
+        let column = rmpv::Value::Map(vec![
+            ("id_hash_".into(), "TXT_PL1500".into()),
+            ("sub_id_hash_".into(), "".into()),
+            ("text_".into(), "Vane, \"the\"\nknight".into()),
+        ]);
+        let file = rmpv::Value::Map(vec![(
+            "rows_".into(),
+            rmpv::Value::Array(vec![rmpv::Value::Map(vec![("column_".into(), column)])]),
+        )]);
+
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &file).unwrap();
+        let language_file = LanguageFile::read(bytes.as_slice()).unwrap();
+
+        let mut output = Vec::new();
+        write_csv(&language_file, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "id,id_hash_,sub_id_hash_,text_\n{:08x},TXT_PL1500,,\"Vane, \"\"the\"\"\nknight\"\n",
+                xxhash32_custom(b"TXT_PL1500")
+            )
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use xxhash32_lib::xxhash32_custom;
+
+use crate::language::Language;
+use crate::language_file::LanguageFile;
+use crate::markup;
+use crate::paths::ExtractPaths;
+
+/// The extractors that ship with language-unpack.
+const DEFAULT_CONFIG: &str = include_str!("../extractors.toml");
+
+pub fn get_value(
+    key: &str,
+    hashmap: &HashMap<String, String>,
+    x32_hashmap: &HashMap<u32, String>,
+) -> Option<String> {
+    let hashed_version = u32::from_str_radix(key, 16)
+        .ok()
+        .and_then(|hashed| x32_hashmap.get(&hashed));
+
+    let decoded_version = hashmap.get(key);
+
+    hashed_version.or(decoded_version).cloned()
+}
+
+fn hash_key_if_not_hashed(key: &str) -> String {
+    let hashed_version = u32::from_str_radix(key, 16).ok();
+
+    if hashed_version.is_none() {
+        format!("{:08x}", xxhash32_custom(key.as_bytes()))
+    } else {
+        key.to_string()
+    }
+}
+
+/// Writes a data file with its keys sorted, so extracting again gives the same file.
+fn write_output(path: &Path, output: &BTreeMap<String, Value>) -> anyhow::Result<()> {
+    let mut output_file = File::create(path).context(format!(
+        "Could not create data file at path: {}",
+        path.display()
+    ))?;
+
+    output_file.write_all(serde_json::to_string_pretty(output)?.as_bytes())?;
+
+    Ok(())
+}
+
+/// How `extract-all` lays out data files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// One file per category in each language's directory, e.g. data/en/weapons.json.
+    #[default]
+    PerLanguage,
+    /// One file per category with every language's text, e.g. data/weapons.json.
+    Merged,
+    /// Both of the above.
+    Both,
+}
+
+/// Writes each language's data files, and merges them into one file per category if asked to.
+pub struct DataWriter<'a> {
+    paths: &'a ExtractPaths,
+    layout: Layout,
+    languages: Vec<Language>,
+    /// Whether text is written with its markup removed.
+    plain_text: bool,
+    /// Each language's data, by file name.
+    merged: BTreeMap<String, BTreeMap<Language, BTreeMap<String, Value>>>,
+}
+
+impl<'a> DataWriter<'a> {
+    pub fn new(
+        paths: &'a ExtractPaths,
+        layout: Layout,
+        languages: &[Language],
+        plain_text: bool,
+    ) -> Self {
+        Self {
+            paths,
+            layout,
+            languages: languages.to_vec(),
+            plain_text,
+            merged: BTreeMap::new(),
+        }
+    }
+
+    /// Creates the directories files are written to.
+    pub fn create_dirs(&self) -> anyhow::Result<()> {
+        let mut dirs = vec![self.paths.output_dir.clone()];
+
+        if self.layout != Layout::Merged {
+            dirs.extend(
+                self.languages
+                    .iter()
+                    .map(|&language| self.paths.language_output_dir(language)),
+            );
+        }
+
+        for dir in dirs {
+            std::fs::create_dir_all(&dir).context(format!(
+                "Could not create output directory at path: {}",
+                dir.display()
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn add(
+        &mut self,
+        language: Language,
+        file_name: &str,
+        mut output: BTreeMap<String, Value>,
+    ) -> anyhow::Result<()> {
+        if self.plain_text {
+            // Text is either the value itself or its `text` field.
+            for value in output.values_mut() {
+                let text = match value {
+                    Value::Object(object) => object.get_mut("text"),
+                    value => Some(value),
+                };
+
+                if let Some(Value::String(text)) = text {
+                    *text = markup::plain_text(text);
+                }
+            }
+        }
+
+        if self.layout != Layout::Merged {
+            write_output(&self.paths.output(language, file_name), &output)?;
+        }
+
+        if self.layout != Layout::PerLanguage {
+            self.merged
+                .entry(file_name.to_string())
+                .or_default()
+                .insert(language, output);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the merged files, where each key maps to `{ key, text: { en, jp, ... } }` with
+    /// null for languages that don't have it.
+    pub fn finish(self) -> anyhow::Result<()> {
+        for (file_name, by_language) in &self.merged {
+            let mut output = BTreeMap::new();
+
+            for (hash, value) in by_language.values().flatten() {
+                if output.contains_key(hash) {
+                    continue;
+                }
+
+                // Characters are keyed by their id and only have text.
+                let key = value
+                    .get("key")
+                    .cloned()
+                    .unwrap_or_else(|| Value::String(hash.clone()));
+
+                let text: BTreeMap<&str, Value> = self
+                    .languages
+                    .iter()
+                    .map(|&language| {
+                        let text = by_language
+                            .get(&language)
+                            .and_then(|data| data.get(hash))
+                            .map(|value| value.get("text").unwrap_or(value).clone())
+                            .unwrap_or(Value::Null);
+
+                        (self.paths.language_name(language), text)
+                    })
+                    .collect();
+
+                output.insert(hash.clone(), json!({ "key": key, "text": text }));
+            }
+
+            write_output(&self.paths.output_dir.join(file_name), &output)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyNormalize {
+    #[default]
+    None,
+    /// Uppercases the first letter and anything after a non-alphanumeric, e.g. EM7700 -> Em7700.
+    TitleCase,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyHash {
+    #[default]
+    Always,
+    /// Keeps keys that are already hex, lowercased.
+    IfNotHashed,
+    Never,
+}
+
+/// One data file built from a system_table query and a .msg file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Extractor {
+    pub output: String,
+    pub msg: String,
+    pub query: String,
+    pub key: String,
+    pub text_id: String,
+    #[serde(default)]
+    pub normalize: KeyNormalize,
+    #[serde(default)]
+    pub hash: KeyHash,
+}
+
+impl Extractor {
+    /// The key a row is written under in the data file.
+    pub fn output_key(&self, key: &str) -> String {
+        let key = match self.normalize {
+            KeyNormalize::None => key.to_string(),
+            KeyNormalize::TitleCase => key
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    if i == 0 || !c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        c.to_ascii_lowercase()
+                    }
+                })
+                .collect(),
+        };
+
+        match self.hash {
+            KeyHash::Always => format!("{:08x}", xxhash32_custom(key.as_bytes())),
+            KeyHash::IfNotHashed => hash_key_if_not_hashed(&key).to_lowercase(),
+            KeyHash::Never => key,
+        }
+    }
+
+    pub fn extract(
+        &self,
+        db: &Connection,
+        paths: &ExtractPaths,
+        languages: &[Language],
+        writer: &mut DataWriter,
+    ) -> anyhow::Result<()> {
+        let mut statement = db
+            .prepare(&self.query)
+            .context(format!("Could not prepare query for {}", self.output))?;
+        let key_column = statement.column_index(&self.key)?;
+        let text_id_column = statement.column_index(&self.text_id)?;
+
+        for &language in languages {
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, Option<String>>(key_column)?,
+                    row.get::<_, Option<String>>(text_id_column)?,
+                ))
+            })?;
+
+            let mut output = BTreeMap::new();
+
+            let lang_file_path = paths.text(language, &self.msg);
+            let lang_file =
+                LanguageFile::open(&lang_file_path.to_string_lossy()).context(format!(
+                    "Could not open language file at path: {}",
+                    lang_file_path.display()
+                ))?;
+
+            let hashmap = lang_file.to_hashmap();
+            let x32_hashmap = lang_file.to_hashed_hashmap();
+
+            for row in rows {
+                let (Some(key), Some(translation_id)) = row? else {
+                    continue;
+                };
+
+                let Some(text) = get_value(&translation_id, &hashmap, &x32_hashmap) else {
+                    continue;
+                };
+
+                if text.is_empty() {
+                    continue;
+                }
+
+                output.insert(
+                    self.output_key(&key),
+                    json!({
+                        "key": key,
+                        "text": text,
+                    }),
+                );
+            }
+
+            writer.add(language, &self.output, output)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractConfig {
+    #[serde(rename = "extractor", default)]
+    pub extractors: Vec<Extractor>,
+}
+
+impl Default for ExtractConfig {
+    fn default() -> Self {
+        toml::from_str(DEFAULT_CONFIG).expect("built-in extractors.toml is valid")
+    }
+}
+
+impl ExtractConfig {
+    /// Loads extractors from a TOML or JSON file, in the same format as `extractors.toml`.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path).context(format!(
+            "Could not read extraction config at path: {}",
+            path.display()
+        ))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&contents).context(format!(
+                "Could not parse extraction config: {}",
+                path.display()
+            )),
+            Some("toml") => toml::from_str(&contents).context(format!(
+                "Could not parse extraction config: {}",
+                path.display()
+            )),
+            _ => bail!(
+                "Expected a .toml or .json extraction config: {}",
+                path.display()
+            ),
+        }
+    }
+
+    /// Every .msg file the extractors read.
+    pub fn msg_files(&self) -> Vec<&str> {
+        self.extractors
+            .iter()
+            .map(|extractor| extractor.msg.as_str())
+            .collect()
+    }
+
+    /// Checks every query runs and has its key and text id columns, listing every problem.
+    pub fn validate(&self, db: &Connection) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+
+        for extractor in &self.extractors {
+            let statement = match db.prepare(&extractor.query) {
+                Ok(statement) => statement,
+                Err(error) => {
+                    problems.push(format!("{}: {}", extractor.output, error));
+                    continue;
+                }
+            };
+
+            for column in [&extractor.key, &extractor.text_id] {
+                if statement.column_index(column).is_err() {
+                    problems.push(format!(
+                        "{}: query has no {} column",
+                        extractor.output, column
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        bail!("Invalid extraction config:\n  {}", problems.join("\n  "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_matches_built_in_extractors() {
+        let config = ExtractConfig::default();
+        let outputs: Vec<&str> = config
+            .extractors
+            .iter()
+            .map(|extractor| extractor.output.as_str())
+            .collect();
+
+        assert_eq!(
+            outputs,
+            vec![
+                "overmasteries.json",
+                "weapons.json",
+                "sigils.json",
+                "traits.json",
+                "items.json",
+                "enemies.json"
+            ]
+        );
+
+        let enemies = &config.extractors[5];
+        assert_eq!(
+            enemies.output_key("EM7700"),
+            format!("{:08x}", xxhash32_custom(b"Em7700"))
+        );
+
+        let overmasteries = &config.extractors[0];
+        assert_eq!(overmasteries.output_key("1A2B3C4D"), "1a2b3c4d");
+    }
+
+    #[test]
+    fn merges_languages_with_nulls() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("data");
+        let paths = ExtractPaths {
+            db: "system_table.sqlite".into(),
+            text_dir: "text".into(),
+            output_dir: output_dir.clone(),
+            bcp47_output: true,
+        };
+
+        let mut writer = DataWriter::new(
+            &paths,
+            Layout::Merged,
+            &[Language::English, Language::Japanese],
+            true,
+        );
+        writer.create_dirs().unwrap();
+
+        let weapon = json!({ "key": "WEP_PL1500_04_03", "text": "<b>Ascalon</b>" });
+        writer
+            .add(
+                Language::English,
+                "weapons.json",
+                BTreeMap::from([("48110ba3".to_string(), weapon)]),
+            )
+            .unwrap();
+        writer
+            .add(Language::Japanese, "weapons.json", BTreeMap::new())
+            .unwrap();
+        writer
+            .add(
+                Language::Japanese,
+                "characters.json",
+                BTreeMap::from([("Pl1500".to_string(), json!("Vane"))]),
+            )
+            .unwrap();
+        writer.finish().unwrap();
+
+        let read = |file_name: &str| -> Value {
+            serde_json::from_str(&std::fs::read_to_string(output_dir.join(file_name)).unwrap())
+                .unwrap()
+        };
+
+        assert_eq!(
+            read("weapons.json"),
+            json!({ "48110ba3": { "key": "WEP_PL1500_04_03", "text": { "en": "Ascalon", "ja": null } } })
+        );
+        assert_eq!(
+            read("characters.json"),
+            json!({ "Pl1500": { "key": "Pl1500", "text": { "en": null, "ja": "Vane" } } })
+        );
+    }
+}
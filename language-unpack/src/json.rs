@@ -0,0 +1,175 @@
+use std::fmt;
+
+use rmpv::Value;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Key of the single-field object 32-bit floats are written as, so packing keeps their width.
+const F32_KEY: &str = "$f32";
+
+/// Serialises a msgpack value as JSON, keeping map fields in order.
+///
+/// Maps must have string keys, and binary, extension and non-finite float values can't be
+/// represented. 32-bit floats are written as `{"$f32": value}`, as JSON numbers have no width.
+pub struct ToJson<'a>(pub &'a Value);
+
+impl Serialize for ToJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Nil => serializer.serialize_unit(),
+            Value::Boolean(value) => serializer.serialize_bool(*value),
+            Value::Integer(value) => match (value.as_u64(), value.as_i64()) {
+                (Some(value), _) => serializer.serialize_u64(value),
+                (None, Some(value)) => serializer.serialize_i64(value),
+                (None, None) => Err(ser::Error::custom("integer out of range")),
+            },
+            Value::F32(value) if value.is_finite() => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(F32_KEY, value)?;
+                map.end()
+            }
+            Value::F64(value) if value.is_finite() => serializer.serialize_f64(*value),
+            Value::F32(_) | Value::F64(_) => Err(ser::Error::custom(format!(
+                "{} can't be written as JSON",
+                self.0
+            ))),
+            Value::String(value) => match value.as_str() {
+                Some(value) => serializer.serialize_str(value),
+                None => Err(ser::Error::custom("string is not valid UTF-8")),
+            },
+            Value::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(&ToJson(value))?;
+                }
+                seq.end()
+            }
+            Value::Map(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    let key = key.as_str().ok_or_else(|| {
+                        ser::Error::custom(format!("map key {} is not a string", key))
+                    })?;
+                    map.serialize_entry(key, &ToJson(value))?;
+                }
+                map.end()
+            }
+            Value::Binary(_) | Value::Ext(..) => Err(ser::Error::custom(format!(
+                "{} can't be written as JSON",
+                self.0
+            ))),
+        }
+    }
+}
+
+/// Deserialises JSON into a msgpack value, keeping map fields in order.
+pub struct FromJson(pub Value);
+
+impl<'de> Deserialize<'de> for FromJson {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor).map(FromJson)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+
+        while let Some(FromJson(value)) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut fields = Vec::new();
+
+        while let Some((key, FromJson(value))) = map.next_entry::<String, FromJson>()? {
+            fields.push((Value::from(key), value));
+        }
+
+        if let [(key, value)] = fields.as_slice() {
+            if key.as_str() == Some(F32_KEY) {
+                return match value.as_f64() {
+                    Some(value) => Ok(Value::F32(value as f32)),
+                    None => Err(de::Error::custom(format!(
+                        "expected {} to be a number, got: {}",
+                        F32_KEY, value
+                    ))),
+                };
+            }
+        }
+
+        Ok(Value::Map(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_in_field_order() {
+        let value = Value::Map(vec![
+            (Value::from("z"), Value::from(-1)),
+            (
+                Value::from("a"),
+                Value::Array(vec![Value::Nil, Value::from(true)]),
+            ),
+            (Value::from("m"), Value::from("text\n")),
+            (Value::from("f"), Value::F32(0.1)),
+            (Value::from("d"), Value::F64(0.1)),
+        ]);
+
+        let json = serde_json::to_string(&ToJson(&value)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"z":-1,"a":[null,true],"m":"text\n","f":{"$f32":0.1},"d":0.1}"#
+        );
+
+        let FromJson(parsed) = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, value);
+
+        let mut written = Vec::new();
+        let mut original = Vec::new();
+        rmpv::encode::write_value(&mut written, &parsed).unwrap();
+        rmpv::encode::write_value(&mut original, &value).unwrap();
+        assert_eq!(written, original);
+
+        assert!(serde_json::to_string(&ToJson(&Value::F64(f64::NAN))).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+
+/// A game language, named by its text directory code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Language {
+    BrazilianPortuguese,
+    SimplifiedChinese,
+    TraditionalChinese,
+    English,
+    Spanish,
+    French,
+    German,
+    Italian,
+    Japanese,
+    Korean,
+}
+
+impl Language {
+    pub const ALL: [Language; 10] = [
+        Language::BrazilianPortuguese,
+        Language::SimplifiedChinese,
+        Language::TraditionalChinese,
+        Language::English,
+        Language::Spanish,
+        Language::French,
+        Language::German,
+        Language::Italian,
+        Language::Japanese,
+        Language::Korean,
+    ];
+
+    /// The game's code, as used for text/{code}/ directories.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::BrazilianPortuguese => "bp",
+            Language::SimplifiedChinese => "cs",
+            Language::TraditionalChinese => "ct",
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::French => "fr",
+            Language::German => "ge",
+            Language::Italian => "it",
+            Language::Japanese => "jp",
+            Language::Korean => "ko",
+        }
+    }
+
+    /// The BCP-47 language tag, as used by web i18n libraries.
+    pub fn bcp47(&self) -> &'static str {
+        match self {
+            Language::BrazilianPortuguese => "pt-BR",
+            Language::SimplifiedChinese => "zh-Hans",
+            Language::TraditionalChinese => "zh-Hant",
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Italian => "it",
+            Language::Japanese => "ja",
+            Language::Korean => "ko",
+        }
+    }
+
+    /// Finds every language with a directory in `text_dir`, in code order.
+    pub fn discover(text_dir: &Path) -> anyhow::Result<Vec<Language>> {
+        let entries = std::fs::read_dir(text_dir).context(format!(
+            "Could not read text directory at path: {}",
+            text_dir.display()
+        ))?;
+        let mut languages = Vec::new();
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if !path.is_dir() {
+                continue;
+            }
+
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            match Language::ALL
+                .iter()
+                .find(|language| language.code() == name)
+            {
+                Some(language) => languages.push(*language),
+                None => eprintln!("Skipping unknown language directory: {}", path.display()),
+            }
+        }
+
+        languages.sort();
+
+        Ok(languages)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    /// Parses either a game code (`jp`) or a BCP-47 tag (`ja`), ignoring case.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Language::ALL
+            .into_iter()
+            .find(|language| {
+                language.code().eq_ignore_ascii_case(input)
+                    || language.bcp47().eq_ignore_ascii_case(input)
+            })
+            .ok_or_else(|| {
+                let codes: Vec<String> = Language::ALL
+                    .iter()
+                    .map(|language| format!("{} ({})", language.code(), language.bcp47()))
+                    .collect();
+
+                format!(
+                    "Unknown language {}, expected one of: {}",
+                    input,
+                    codes.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_game_codes_and_tags() {
+        assert_eq!("jp".parse(), Ok(Language::Japanese));
+        assert_eq!("ja".parse(), Ok(Language::Japanese));
+        assert_eq!("zh-hant".parse(), Ok(Language::TraditionalChinese));
+        assert_eq!("ge".parse::<Language>().unwrap().bcp47(), "de");
+        assert!("xx".parse::<Language>().is_err());
+    }
+}
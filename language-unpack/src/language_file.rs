@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+
+use anyhow::{bail, Context};
+use rmpv::Value;
+use xxhash32_lib::xxhash32_custom;
+
+/// Fields of a msgpack map, in file order.
+type Fields = Vec<(Value, Value)>;
+
+fn into_fields(value: Value, what: &str) -> anyhow::Result<Fields> {
+    match value {
+        Value::Map(fields) => Ok(fields),
+        other => bail!("Expected {} to be a map, got: {}", what, other),
+    }
+}
+
+fn find<'a>(fields: &'a Fields, key: &str) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(field, _)| field.as_str() == Some(key))
+        .map(|(_, value)| value)
+}
+
+fn find_string(fields: &Fields, key: &str) -> anyhow::Result<Option<String>> {
+    match find(fields, key) {
+        None => Ok(None),
+        Some(Value::String(value)) => match value.as_str() {
+            Some(value) => Ok(Some(value.to_string())),
+            None => bail!("Expected {} to be UTF-8", key),
+        },
+        Some(other) => bail!("Expected {} to be a string, got: {}", key, other),
+    }
+}
+
+/// Converts an edited field back to the type it had in the file, so editing a non-string
+/// `sub_id_hash_` doesn't change its msgpack type.
+fn typed_like(original: &Value, current: &str) -> anyhow::Result<Value> {
+    let value = match original {
+        Value::String(_) => Value::from(current),
+        value if value.to_string() == current => value.clone(),
+        Value::Integer(_) => match (current.parse::<u64>(), current.parse::<i64>()) {
+            (Ok(integer), _) => Value::from(integer),
+            (_, Ok(integer)) => Value::from(integer),
+            _ => bail!(
+                "Expected {:?} to be an integer, like the original {}",
+                current,
+                original
+            ),
+        },
+        Value::Boolean(_) => match current.parse::<bool>() {
+            Ok(boolean) => Value::from(boolean),
+            Err(_) => bail!(
+                "Expected {:?} to be a boolean, like the original {}",
+                current,
+                original
+            ),
+        },
+        _ => bail!("Can't write {:?} in place of {}", current, original),
+    };
+
+    Ok(value)
+}
+
+/// An empty value of the same msgpack type, for fields of a new row copied from another row.
+fn empty_like(value: &Value) -> Value {
+    match value {
+        Value::Nil => Value::Nil,
+        Value::Boolean(_) => Value::from(false),
+        Value::Integer(_) => Value::from(0),
+        Value::F32(_) => Value::F32(0.0),
+        Value::F64(_) => Value::F64(0.0),
+        Value::String(_) => Value::from(""),
+        Value::Binary(_) => Value::Binary(Vec::new()),
+        Value::Array(_) => Value::Array(Vec::new()),
+        Value::Map(_) => Value::Map(Vec::new()),
+        Value::Ext(kind, _) => Value::Ext(*kind, Vec::new()),
+    }
+}
+
+fn fields_like(template: &Fields) -> Fields {
+    template
+        .iter()
+        .map(|(key, value)| (key.clone(), empty_like(value)))
+        .collect()
+}
+
+/// The columns of a text row.
+///
+/// Every field is kept in file order, so rows are written back exactly as they were read, and
+/// only `id_hash_`, `sub_id_hash_` and `text_` are replaced by their (possibly edited) values.
+#[derive(Clone, PartialEq)]
+pub struct LanguageRowColumn {
+    pub id_hash_: String,
+    pub sub_id_hash_: String,
+    pub text_: String,
+    fields: Fields,
+}
+
+impl LanguageRowColumn {
+    pub fn new(id_hash_: &str, sub_id_hash_: &str, text_: &str) -> Self {
+        Self {
+            id_hash_: id_hash_.to_string(),
+            sub_id_hash_: sub_id_hash_.to_string(),
+            text_: text_.to_string(),
+            fields: ["id_hash_", "sub_id_hash_", "text_"]
+                .into_iter()
+                .map(|key| (Value::from(key), Value::from("")))
+                .collect(),
+        }
+    }
+
+    /// A column with the same fields as `template`, in the same order, set to empty values of
+    /// the same types. `sub_id_hash_` is empty too, written with the template's type.
+    pub fn like(template: &LanguageRowColumn, id_hash_: &str, text_: &str) -> Self {
+        let fields = fields_like(&template.fields);
+        let sub_id_hash_ = match find(&fields, "sub_id_hash_") {
+            Some(Value::String(_)) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+
+        Self {
+            id_hash_: id_hash_.to_string(),
+            sub_id_hash_,
+            text_: text_.to_string(),
+            fields,
+        }
+    }
+
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        let fields = into_fields(value, "column_")?;
+
+        // sub_id_hash_ isn't used by the extractors, so it's kept as-is if it isn't a string.
+        let sub_id_hash_ = match find(&fields, "sub_id_hash_") {
+            Some(Value::String(_)) | None => {
+                find_string(&fields, "sub_id_hash_")?.unwrap_or_default()
+            }
+            Some(other) => other.to_string(),
+        };
+
+        let id_hash_ = find_string(&fields, "id_hash_")?.context("Row is missing id_hash_")?;
+        let text_ = find_string(&fields, "text_")?.context("Row is missing text_")?;
+
+        // String values are written back from the typed fields, so only their position is kept.
+        let fields = fields
+            .into_iter()
+            .map(|(key, value)| match (key.as_str(), value) {
+                (Some("id_hash_" | "sub_id_hash_" | "text_"), Value::String(_)) => {
+                    (key, Value::from(""))
+                }
+                (_, value) => (key, value),
+            })
+            .collect();
+
+        Ok(Self {
+            id_hash_,
+            sub_id_hash_,
+            text_,
+            fields,
+        })
+    }
+
+    fn to_value(&self) -> anyhow::Result<Value> {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| {
+                let current = match key.as_str() {
+                    Some("id_hash_") => &self.id_hash_,
+                    Some("sub_id_hash_") => &self.sub_id_hash_,
+                    Some("text_") => &self.text_,
+                    _ => return Ok((key.clone(), value.clone())),
+                };
+
+                Ok((key.clone(), typed_like(value, current)?))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Value::Map(fields))
+    }
+
+    /// Fields other than `id_hash_`, `sub_id_hash_` and `text_`, in file order.
+    pub fn other_fields(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.fields
+            .iter()
+            .filter(|(key, _)| {
+                !matches!(
+                    key.as_str(),
+                    Some("id_hash_") | Some("sub_id_hash_") | Some("text_")
+                )
+            })
+            .map(|(key, value)| (key, value))
+    }
+}
+
+impl fmt::Debug for LanguageRowColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("LanguageRowColumn");
+        debug
+            .field("id_hash_", &self.id_hash_)
+            .field("sub_id_hash_", &self.sub_id_hash_)
+            .field("text_", &self.text_);
+
+        for (key, value) in self.other_fields() {
+            debug.field(key.as_str().unwrap_or("?"), &format_args!("{}", value));
+        }
+
+        debug.finish()
+    }
+}
+
+/// A text row, keeping any fields besides `column_`.
+#[derive(Clone, PartialEq)]
+pub struct LanguageRow {
+    pub column_: LanguageRowColumn,
+    fields: Fields,
+}
+
+impl LanguageRow {
+    pub fn new(column_: LanguageRowColumn) -> Self {
+        Self {
+            column_,
+            fields: vec![(Value::from("column_"), Value::Nil)],
+        }
+    }
+
+    /// A row with the same fields as `template`, like `LanguageRowColumn::like`.
+    pub fn like(template: &LanguageRow, id_hash_: &str, text_: &str) -> Self {
+        Self {
+            column_: LanguageRowColumn::like(&template.column_, id_hash_, text_),
+            fields: fields_like(&template.fields),
+        }
+    }
+
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        let fields = into_fields(value, "row")?;
+        let column_ = find(&fields, "column_")
+            .cloned()
+            .context("Row is missing column_")?;
+
+        let fields = fields
+            .into_iter()
+            .map(|(key, value)| match key.as_str() {
+                Some("column_") => (key, Value::Nil),
+                _ => (key, value),
+            })
+            .collect();
+
+        Ok(Self {
+            column_: LanguageRowColumn::from_value(column_)?,
+            fields,
+        })
+    }
+
+    fn to_value(&self) -> anyhow::Result<Value> {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| match key.as_str() {
+                Some("column_") => Ok((key.clone(), self.column_.to_value()?)),
+                _ => Ok((key.clone(), value.clone())),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Value::Map(fields))
+    }
+}
+
+impl fmt::Debug for LanguageRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("LanguageRow");
+        debug.field("column_", &self.column_);
+
+        for (key, value) in &self.fields {
+            if key.as_str() != Some("column_") {
+                debug.field(key.as_str().unwrap_or("?"), &format_args!("{}", value));
+            }
+        }
+
+        debug.finish()
+    }
+}
+
+/// A text table .msg file, which round-trips without losing fields or their order.
+///
+/// Files are read as `rmpv::Value`s rather than through rmp-serde, which would only keep the
+/// fields of a fixed struct and re-encode values with the struct's types instead of the file's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageFile {
+    pub rows_: Vec<LanguageRow>,
+    fields: Fields,
+}
+
+impl LanguageFile {
+    pub fn open(file_path: &str) -> anyhow::Result<Self> {
+        let file = File::open(file_path)?;
+        Self::read(BufReader::new(file))
+    }
+
+    pub fn new(rows_: Vec<LanguageRow>) -> Self {
+        Self {
+            rows_,
+            fields: vec![(Value::from("rows_"), Value::Nil)],
+        }
+    }
+
+    pub fn read(mut reader: impl Read) -> anyhow::Result<Self> {
+        Self::from_value(rmpv::decode::read_value(&mut reader)?)
+    }
+
+    pub fn from_value(value: Value) -> anyhow::Result<Self> {
+        let fields = into_fields(value, "language file")?;
+
+        let rows_ = match find(&fields, "rows_") {
+            Some(Value::Array(rows)) => rows
+                .iter()
+                .cloned()
+                .map(LanguageRow::from_value)
+                .collect::<anyhow::Result<_>>()?,
+            Some(other) => bail!("Expected rows_ to be an array, got: {}", other),
+            None => bail!("Language file is missing rows_"),
+        };
+
+        // The rows are rebuilt from `rows_` when writing, so there's no need to keep two copies.
+        let fields = fields
+            .into_iter()
+            .map(|(key, value)| match key.as_str() {
+                Some("rows_") => (key, Value::Nil),
+                _ => (key, value),
+            })
+            .collect();
+
+        Ok(Self { rows_, fields })
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        rmpv::encode::write_value(&mut writer, &self.to_value()?)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn to_value(&self) -> anyhow::Result<Value> {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(key, value)| match key.as_str() {
+                Some("rows_") => Ok((
+                    key.clone(),
+                    Value::Array(
+                        self.rows_
+                            .iter()
+                            .map(LanguageRow::to_value)
+                            .collect::<anyhow::Result<_>>()?,
+                    ),
+                )),
+                _ => Ok((key.clone(), value.clone())),
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Value::Map(fields))
+    }
+
+    pub fn to_hashmap(&self) -> HashMap<String, String> {
+        let mut hashmap = HashMap::new();
+
+        for row in &self.rows_ {
+            hashmap.insert(row.column_.id_hash_.clone(), row.column_.text_.clone());
+        }
+
+        hashmap
+    }
+
+    pub fn to_hashed_hashmap(&self) -> HashMap<u32, String> {
+        let mut hashmap = HashMap::new();
+
+        for row in &self.rows_ {
+            let hash = xxhash32_custom(row.column_.id_hash_.as_bytes());
+            hashmap.insert(hash, row.column_.text_.clone());
+        }
+
+        hashmap
+    }
+
+    /// Top-level fields other than `rows_`, in file order.
+    pub fn other_fields(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.fields
+            .iter()
+            .filter(|(key, _)| key.as_str() != Some("rows_"))
+            .map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(test)]
+impl LanguageFile {
+    /// A file of `(id_hash_, text_)` rows with an empty `sub_id_hash_`.
+    pub fn from_texts(rows: &[(&str, &str)]) -> Self {
+        Self::new(
+            rows.iter()
+                .map(|(id_hash_, text_)| {
+                    LanguageRow::new(LanguageRowColumn::new(id_hash_, "", text_))
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(value: &str) -> Value {
+        Value::from(value)
+    }
+
+    #[test]
+    fn round_trips_unknown_fields_in_order() {
+        let column = Value::Map(vec![
+            (string("text_"), string("Vane")),
+            (string("flag_"), Value::from(3)),
+            (string("sub_id_hash_"), Value::from(7)),
+            (string("id_hash_"), string("TXT_PL1500")),
+        ]);
+        let file = Value::Map(vec![
+            (string("version_"), Value::from(2)),
+            (
+                string("rows_"),
+                Value::Array(vec![Value::Map(vec![
+                    (string("column_"), column),
+                    (string("index_"), Value::from(0)),
+                ])]),
+            ),
+            (string("name_"), string("text")),
+        ]);
+
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &file).unwrap();
+
+        let language_file = LanguageFile::read(bytes.as_slice()).unwrap();
+        let column = &language_file.rows_[0].column_;
+        assert_eq!(column.id_hash_, "TXT_PL1500");
+        assert_eq!(column.sub_id_hash_, "7");
+        assert_eq!(column.other_fields().count(), 1);
+
+        let mut written = Vec::new();
+        language_file.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+
+        let mut edited = language_file.clone();
+        edited.rows_[0].column_.text_ = "Lancelot".to_string();
+        edited.rows_[0].column_.sub_id_hash_ = "8".to_string();
+
+        let mut written = Vec::new();
+        edited.write(&mut written).unwrap();
+        let reread = LanguageFile::read(written.as_slice()).unwrap();
+        assert_eq!(reread.rows_[0].column_.text_, "Lancelot");
+        assert_eq!(reread.rows_[0].column_.sub_id_hash_, "8");
+        assert_eq!(reread.other_fields().count(), 2);
+
+        let value = reread.to_value().unwrap();
+        let rows = value.as_map().unwrap()[1].1.as_array().unwrap();
+        let column = rows[0].as_map().unwrap()[0].1.as_map().unwrap();
+        assert_eq!(column[2], (string("sub_id_hash_"), Value::from(8)));
+
+        edited.rows_[0].column_.sub_id_hash_ = "TXT_SUB".to_string();
+        assert!(edited.write(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+use crate::diff::row_name;
+use crate::language::Language;
+use crate::markup::{self, Piece};
+use crate::texts::TextTree;
+
+/// Count of each `<tag>`, `</tag>` and `{placeholder}` in a text.
+pub type Markup = BTreeMap<String, usize>;
+
+/// Collects the markup in `text`, leaving out tags named in `ignore_tags`. Tags are counted as
+/// written rather than parsed, so a dropped closing tag is a mismatch.
+pub fn markup(text: &str, ignore_tags: &[String]) -> Markup {
+    let mut markup = Markup::new();
+
+    for piece in markup::pieces(text) {
+        let token = match piece {
+            Piece::Text(_) => continue,
+            Piece::Tag(tag) => tag,
+            Piece::Placeholder(placeholder) => placeholder,
+        };
+
+        if piece
+            .tag_name()
+            .is_some_and(|name| ignore_tags.iter().any(|tag| tag == name))
+        {
+            continue;
+        }
+
+        *markup.entry(token.to_string()).or_default() += 1;
+    }
+
+    markup
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub file: String,
+    pub id_hash_: String,
+    pub sub_id_hash_: String,
+    /// Languages grouped by the markup their text has.
+    pub groups: Vec<(Markup, Vec<Language>)>,
+}
+
+/// `id_hash_`, `sub_id_hash_` and how many rows with that pair came before it.
+type RowKey<'a> = (&'a str, &'a str, usize);
+
+/// Finds rows whose markup isn't the same in every language that has them. Rows are matched by
+/// `id_hash_` and `sub_id_hash_`, and rows sharing a key are compared in the order they appear.
+pub fn find_mismatches(
+    tree: &TextTree,
+    languages: &[Language],
+    ignore_tags: &[String],
+) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+
+    for (file, copies) in &tree.files {
+        let mut rows: BTreeMap<RowKey, Vec<(Markup, Language)>> = BTreeMap::new();
+
+        for &language in languages {
+            let Some(language_file) = copies.get(&language) else {
+                continue;
+            };
+            let mut occurrences: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+
+            for row in &language_file.rows_ {
+                let column = &row.column_;
+                let key = (column.id_hash_.as_str(), column.sub_id_hash_.as_str());
+                let occurrence = occurrences.entry(key).or_default();
+
+                rows.entry((key.0, key.1, *occurrence))
+                    .or_default()
+                    .push((markup(&column.text_, ignore_tags), language));
+                *occurrence += 1;
+            }
+        }
+
+        for ((id_hash_, sub_id_hash_, _), markups) in rows {
+            let mut groups: Vec<(Markup, Vec<Language>)> = Vec::new();
+
+            for (markup, language) in markups {
+                match groups.iter_mut().find(|(group, _)| *group == markup) {
+                    Some((_, languages)) => languages.push(language),
+                    None => groups.push((markup, vec![language])),
+                }
+            }
+
+            if groups.len() > 1 {
+                // The most common markup first, so the odd languages out come last.
+                groups.sort_by_key(|(_, languages)| std::cmp::Reverse(languages.len()));
+
+                mismatches.push(Mismatch {
+                    file: file.clone(),
+                    id_hash_: id_hash_.to_string(),
+                    sub_id_hash_: sub_id_hash_.to_string(),
+                    groups,
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+impl Mismatch {
+    /// The row followed by each group of languages and their markup, one per line.
+    pub fn to_terminal(&self) -> String {
+        let mut output = format!(
+            "{} {}:\n",
+            self.file,
+            row_name(&self.id_hash_, &self.sub_id_hash_)
+        );
+
+        for (markup, languages) in &self.groups {
+            let languages: Vec<String> = languages.iter().map(Language::to_string).collect();
+            let tokens: Vec<String> = markup
+                .iter()
+                .map(|(token, count)| match count {
+                    1 => token.clone(),
+                    count => format!("{}×{}", token, count),
+                })
+                .collect();
+            let tokens = if tokens.is_empty() {
+                "(no markup)".to_string()
+            } else {
+                tokens.join(" ")
+            };
+
+            output.push_str(&format!("  {}: {}\n", languages.join(", "), tokens));
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language_file::{LanguageFile, LanguageRow, LanguageRowColumn};
+
+    #[test]
+    fn groups_languages_by_markup() {
+        let mut copies = BTreeMap::new();
+        copies.insert(
+            Language::English,
+            LanguageFile::from_texts(&[
+                ("TXT_A", "{0} <color=red>hot</color>"),
+                ("TXT_B", "{0}, {1}"),
+            ]),
+        );
+        copies.insert(
+            Language::French,
+            LanguageFile::from_texts(&[
+                ("TXT_A", "<color=red>chaud</color> {0}"),
+                ("TXT_B", "{1} {0}"),
+            ]),
+        );
+        copies.insert(
+            Language::Japanese,
+            LanguageFile::from_texts(&[("TXT_A", "<ruby=あつ>熱</ruby>い {0}"), ("TXT_B", "{0}")]),
+        );
+
+        let mut tree = TextTree {
+            files: BTreeMap::new(),
+        };
+        tree.files.insert("text.msg".to_string(), copies);
+
+        let ignore_tags = vec!["ruby".to_string()];
+        assert_eq!(
+            markup("<ruby=あつ>熱</ruby>い {0}{0}", &ignore_tags),
+            [("{0}".to_string(), 2)].into()
+        );
+
+        let mismatches = find_mismatches(&tree, &Language::ALL, &ignore_tags);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].id_hash_, "TXT_A");
+        assert_eq!(
+            mismatches[0].groups[1],
+            ([("{0}".to_string(), 1)].into(), vec![Language::Japanese])
+        );
+        assert_eq!(
+            mismatches[1].groups[0].1,
+            vec![Language::English, Language::French]
+        );
+        assert_eq!(
+            mismatches[1].to_terminal(),
+            "text.msg TXT_B:\n  en, fr: {0} {1}\n  jp: {0}\n"
+        );
+    }
+
+    #[test]
+    fn matches_rows_by_sub_id_and_order() {
+        let language_file = |rows: &[(&str, &str, &str)]| {
+            LanguageFile::new(
+                rows.iter()
+                    .map(|(id_hash_, sub_id_hash_, text_)| {
+                        LanguageRow::new(LanguageRowColumn::new(id_hash_, sub_id_hash_, text_))
+                    })
+                    .collect(),
+            )
+        };
+
+        let mut copies = BTreeMap::new();
+        copies.insert(
+            Language::English,
+            language_file(&[
+                ("TXT_C", "1", "{0}"),
+                ("TXT_C", "2", "{0} {1}"),
+                ("TXT_D", "", "{0}"),
+                ("TXT_D", "", "<b>{0}</b>"),
+            ]),
+        );
+        copies.insert(
+            Language::French,
+            language_file(&[
+                ("TXT_C", "2", "{1}"),
+                ("TXT_C", "1", "{0}"),
+                ("TXT_D", "", "{0}"),
+                ("TXT_D", "", "{0}"),
+            ]),
+        );
+
+        let mut tree = TextTree {
+            files: BTreeMap::new(),
+        };
+        tree.files.insert("text.msg".to_string(), copies);
+
+        let mismatches = find_mismatches(&tree, &Language::ALL, &[]);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(
+            mismatches[0].to_terminal(),
+            "text.msg TXT_C (2):\n  en: {0} {1}\n  fr: {1}\n"
+        );
+        assert_eq!(
+            mismatches[1].to_terminal(),
+            "text.msg TXT_D:\n  en: </b> <b> {0}\n  fr: {0}\n"
+        );
+    }
+}
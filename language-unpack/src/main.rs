@@ -1,93 +1,46 @@
-use std::collections::HashMap;
+mod coverage;
+mod diff;
+mod export;
+mod extract;
+mod json;
+mod language;
+mod language_file;
+mod lint;
+mod markup;
+mod pack;
+mod patch;
+mod paths;
+mod texts;
+mod untranslated;
+
+use std::collections::BTreeMap;
 
 use anyhow::Context;
 use clap::{Parser, Subcommand};
+use coverage::{CoverageFormat, CoverageReport};
+use diff::{Diff, DiffFormat};
+use export::ExportFormat;
+use extract::{DataWriter, ExtractConfig, Layout};
+use language::Language;
+use language_file::LanguageFile;
+use paths::{ExtractPaths, TextArgs};
 use rusqlite::Connection;
-use serde::Deserialize;
-use serde_json::{json, Map, Value};
-use std::{
-    fs::File,
-    io::{BufReader, Write},
-    path::PathBuf,
-};
-use xxhash32_lib::xxhash32_custom;
-
-#[derive(Debug, Deserialize)]
-struct LanguageRowColumn {
-    id_hash_: String,
-    text_: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct LanguageRow {
-    column_: LanguageRowColumn,
-}
-
-#[derive(Debug, Deserialize)]
-struct LanguageFile {
-    rows_: Vec<LanguageRow>,
-}
-
-impl LanguageFile {
-    pub fn open(file_path: &str) -> anyhow::Result<Self> {
-        let file = File::open(file_path)?;
-        let mut reader = BufReader::new(file);
-        let lang_file = rmp_serde::from_read(&mut reader)?;
-        Ok(lang_file)
-    }
+use serde_json::{json, Value};
+use std::{fs::File, path::PathBuf};
+use untranslated::Allowlist;
+use xxhash32_lib::index::HashIndex;
 
-    pub fn to_hashmap(&self) -> HashMap<String, String> {
-        let mut hashmap = HashMap::new();
-
-        for row in &self.rows_ {
-            hashmap.insert(row.column_.id_hash_.clone(), row.column_.text_.clone());
-        }
-
-        hashmap
-    }
-
-    pub fn to_hashed_hashmap(&self) -> HashMap<u32, String> {
-        let mut hashmap = HashMap::new();
-
-        for row in &self.rows_ {
-            let hash = xxhash32_custom(row.column_.id_hash_.as_bytes());
-            hashmap.insert(hash, row.column_.text_.clone());
-        }
-
-        hashmap
-    }
-}
-
-fn get_value(
-    key: &str,
-    hashmap: &HashMap<String, String>,
-    x32_hashmap: &HashMap<u32, String>,
-) -> Option<String> {
-    let hashed_version = u32::from_str_radix(key, 16)
-        .ok()
-        .and_then(|hashed| x32_hashmap.get(&hashed));
-
-    let decoded_version = hashmap.get(key);
-
-    hashed_version.or(decoded_version).cloned()
-}
-
-fn hash_key_if_not_hashed(key: &str) -> String {
-    let hashed_version = u32::from_str_radix(key, 16).ok();
-
-    if hashed_version.is_none() {
-        format!("{:08x}", xxhash32_custom(key.as_bytes()))
-    } else {
-        key.to_string()
-    }
-}
-
-const LANGUAGES: [&str; 10] = ["bp", "cs", "ct", "en", "es", "fr", "ge", "it", "jp", "ko"];
+/// .msg files read by the extractors that aren't in the extraction config.
+const BUILT_IN_MSG_FILES: [&str; 2] = ["text_chara.msg", "text_stage.msg"];
 
 struct Characters;
 
 impl Characters {
-    pub fn extract() -> anyhow::Result<()> {
+    pub fn extract(
+        paths: &ExtractPaths,
+        languages: &[Language],
+        writer: &mut DataWriter,
+    ) -> anyhow::Result<()> {
         let characters = [
             ("Pl0000", "TXT_PL0000"),
             ("Pl0100", "TXT_PL0100"),
@@ -116,13 +69,14 @@ impl Characters {
             ("Pl2400", "TXT_PL2400"),
         ];
 
-        for language in LANGUAGES {
-            let mut output = Map::new();
-            let lang_file_path = format!("text/{}/text_chara.msg", language);
-            let language_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
+        for &language in languages {
+            let mut output = BTreeMap::new();
+            let lang_file_path = paths.text(language, "text_chara.msg");
+            let language_file =
+                LanguageFile::open(&lang_file_path.to_string_lossy()).context(format!(
+                    "Could not open language file at path: {}",
+                    lang_file_path.display()
+                ))?;
             let hashmap = language_file.to_hashmap();
 
             for (id, key) in characters {
@@ -131,276 +85,7 @@ impl Characters {
                 output.insert(id.to_string(), Value::String(text.to_string()));
             }
 
-            let mut output_file =
-                File::create(format!("data/{}/characters.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
-        }
-
-        Ok(())
-    }
-}
-
-struct Overmastery;
-
-impl Overmastery {
-    fn extract(db: &Connection) -> anyhow::Result<()> {
-        let mut statement =
-            db.prepare("SELECT Key, FullName FROM limit_bonus_param WHERE FullName IS NOT NULL")?;
-
-        for language in LANGUAGES {
-            let rows = statement.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?;
-
-            let mut output = Map::new();
-            let lang_file_path = format!("text/{}/text_limit_bonus.msg", language);
-            let lang_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
-
-            let hashmap = lang_file.to_hashmap();
-            let x32_hashmap = lang_file.to_hashed_hashmap();
-
-            for row in rows {
-                let (key, translation_id) = row.unwrap();
-                let text = get_value(&translation_id, &hashmap, &x32_hashmap);
-
-                if let Some(text) = text {
-                    if text.is_empty() {
-                        continue;
-                    }
-
-                    let hashed_key = hash_key_if_not_hashed(&key);
-
-                    output.insert(
-                        hashed_key.to_lowercase(),
-                        json!({
-                            "key": key,
-                            "text": text,
-                        }),
-                    );
-                }
-            }
-
-            let mut output_file =
-                File::create(format!("data/{}/overmasteries.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
-        }
-
-        Ok(())
-    }
-}
-
-struct Weapon;
-
-impl Weapon {
-    pub fn extract(db: &Connection) -> anyhow::Result<()> {
-        let mut statement =
-            db.prepare("SELECT Key, Name FROM weapon WHERE Name IS NOT NULL AND Key IS NOT NULL")?;
-
-        for language in LANGUAGES {
-            let rows = statement.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?;
-
-            let mut output = Map::new();
-
-            let lang_file_path = format!("text/{}/text.msg", language);
-            let lang_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
-
-            let hashmap = lang_file.to_hashmap();
-            let x32_hashmap = lang_file.to_hashed_hashmap();
-
-            for row in rows {
-                let (key, translation_id) = row.unwrap();
-                let text = get_value(&translation_id, &hashmap, &x32_hashmap);
-
-                if let Some(text) = text {
-                    if text.is_empty() {
-                        continue;
-                    }
-
-                    let hash = format!("{:08x}", xxhash32_custom(key.as_bytes()));
-
-                    output.insert(
-                        hash,
-                        json!({
-                            "key": key,
-                            "text": text,
-                        }),
-                    );
-                }
-            }
-
-            let mut output_file = File::create(format!("data/{}/weapons.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
-        }
-
-        Ok(())
-    }
-}
-
-struct Sigils;
-
-impl Sigils {
-    fn extract(db: &Connection) -> anyhow::Result<()> {
-        let mut statement =
-            db.prepare("SELECT Key, Name FROM gem WHERE Name IS NOT NULL AND Key IS NOT NULL")?;
-
-        for language in LANGUAGES {
-            let rows = statement.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?;
-
-            let mut output = Map::new();
-
-            let lang_file_path = format!("text/{}/text.msg", language);
-            let lang_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
-
-            let hashmap = lang_file.to_hashmap();
-            let x32_hashmap = lang_file.to_hashed_hashmap();
-
-            for row in rows {
-                let (key, translation_id) = row.unwrap();
-                let text = get_value(&translation_id, &hashmap, &x32_hashmap);
-
-                if let Some(text) = text {
-                    if text.is_empty() {
-                        continue;
-                    }
-
-                    let hash = format!("{:08x}", xxhash32_custom(key.as_bytes()));
-
-                    output.insert(
-                        hash,
-                        json!({
-                            "key": key,
-                            "text": text,
-                        }),
-                    );
-                }
-            }
-
-            let mut output_file = File::create(format!("data/{}/sigils.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
-        }
-
-        Ok(())
-    }
-}
-
-struct Traits;
-
-impl Traits {
-    fn extract(db: &Connection) -> anyhow::Result<()> {
-        let mut statement =
-            db.prepare("SELECT Key, Name FROM skill WHERE Name IS NOT NULL AND Key IS NOT NULL")?;
-
-        for language in LANGUAGES {
-            let rows = statement.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?;
-
-            let mut output = Map::new();
-
-            let lang_file_path = format!("text/{}/text.msg", language);
-            let lang_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
-
-            let hashmap = lang_file.to_hashmap();
-            let x32_hashmap = lang_file.to_hashed_hashmap();
-
-            for row in rows {
-                let (key, translation_id) = row.unwrap();
-                let text = get_value(&translation_id, &hashmap, &x32_hashmap);
-
-                if let Some(text) = text {
-                    if text.is_empty() {
-                        continue;
-                    }
-
-                    let hash = format!("{:08x}", xxhash32_custom(key.as_bytes()));
-
-                    output.insert(
-                        hash,
-                        json!({
-                            "key": key,
-                            "text": text,
-                        }),
-                    );
-                }
-            }
-
-            let mut output_file = File::create(format!("data/{}/traits.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
-        }
-
-        Ok(())
-    }
-}
-
-struct Items;
-
-impl Items {
-    fn extract(db: &Connection) -> anyhow::Result<()> {
-        let mut statement = db.prepare(
-            "SELECT Key, ItemName FROM item WHERE ItemName IS NOT NULL AND Key IS NOT NULL",
-        )?;
-
-        for language in LANGUAGES {
-            let rows = statement.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?;
-
-            let mut output = Map::new();
-
-            let lang_file_path = format!("text/{}/text.msg", language);
-            let lang_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
-
-            let hashmap = lang_file.to_hashmap();
-            let x32_hashmap = lang_file.to_hashed_hashmap();
-
-            for row in rows {
-                let (key, translation_id) = row.unwrap();
-                let text = get_value(&translation_id, &hashmap, &x32_hashmap);
-
-                if let Some(text) = text {
-                    if text.is_empty() {
-                        continue;
-                    }
-
-                    let hash = format!("{:08x}", xxhash32_custom(key.as_bytes()));
-
-                    output.insert(
-                        hash,
-                        json!({
-                            "key": key,
-                            "text": text,
-                        }),
-                    );
-                }
-            }
-
-            let mut output_file = File::create(format!("data/{}/items.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
+            writer.add(language, "characters.json", output)?;
         }
 
         Ok(())
@@ -410,14 +95,19 @@ impl Items {
 struct Quests;
 
 impl Quests {
-    fn extract() -> anyhow::Result<()> {
-        for language in LANGUAGES {
-            let mut output = Map::new();
-            let lang_file_path = format!("text/{}/text_stage.msg", language);
-            let language_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
+    fn extract(
+        paths: &ExtractPaths,
+        languages: &[Language],
+        writer: &mut DataWriter,
+    ) -> anyhow::Result<()> {
+        for &language in languages {
+            let mut output = BTreeMap::new();
+            let lang_file_path = paths.text(language, "text_stage.msg");
+            let language_file =
+                LanguageFile::open(&lang_file_path.to_string_lossy()).context(format!(
+                    "Could not open language file at path: {}",
+                    lang_file_path.display()
+                ))?;
 
             for row in &language_file.rows_ {
                 if !row.column_.id_hash_.starts_with("TXT_QR") {
@@ -437,78 +127,7 @@ impl Quests {
                 }
             }
 
-            let mut output_file = File::create(format!("data/{}/quests.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
-        }
-
-        Ok(())
-    }
-}
-
-struct Enemies;
-
-impl Enemies {
-    fn extract(db: &Connection) -> anyhow::Result<()> {
-        let mut statement = db.prepare(
-            "SELECT KeyMaybe, VariantName1 FROM enemy WHERE KeyMaybe IS NOT NULL AND VariantName1 IS NOT NULL",
-        )?;
-
-        for language in LANGUAGES {
-            let rows = statement.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-            })?;
-
-            let mut output = Map::new();
-
-            let lang_file_path = format!("text/{}/text_chara.msg", language);
-            let lang_file = LanguageFile::open(&lang_file_path).context(format!(
-                "Could not open language file at path: {}",
-                &lang_file_path
-            ))?;
-
-            let hashmap = lang_file.to_hashmap();
-            let x32_hashmap = lang_file.to_hashed_hashmap();
-
-            for row in rows {
-                let (key, translation_id) = row.unwrap();
-                let text = get_value(&translation_id, &hashmap, &x32_hashmap);
-
-                if let Some(text) = text {
-                    if text.is_empty() {
-                        continue;
-                    }
-
-                    // Convert enemy name to titlecase, EM7700 -> Em7700
-                    let mut enemy_name = key.clone();
-
-                    enemy_name = enemy_name
-                        .chars()
-                        .enumerate()
-                        .map(|(i, c)| {
-                            if i == 0 || !c.is_ascii_alphanumeric() {
-                                c.to_ascii_uppercase()
-                            } else {
-                                c.to_ascii_lowercase()
-                            }
-                        })
-                        .collect();
-
-                    let hash = format!("{:08x}", xxhash32_custom(enemy_name.as_bytes()));
-
-                    output.insert(
-                        hash,
-                        json!({
-                            "key": key,
-                            "text": text,
-                        }),
-                    );
-                }
-            }
-
-            let mut output_file = File::create(format!("data/{}/enemies.json", language)).unwrap();
-
-            output_file.write(&serde_json::to_string_pretty(&output)?.as_bytes())?;
+            writer.add(language, "quests.json", output)?;
         }
 
         Ok(())
@@ -524,41 +143,319 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Print { file: PathBuf },
-    ExtractAll {},
+    Print {
+        file: PathBuf,
+
+        /// Hash index built with `xxhash32 build-index`, used to name hashed id_hash_ values.
+        #[arg(long)]
+        names: Option<PathBuf>,
+    },
+    /// Exports a .msg file, or every .msg file in a directory, as id,id_hash_,sub_id_hash_,text_ CSVs.
+    ExportCsv {
+        path: PathBuf,
+
+        /// Output file, or output directory when exporting a directory. Defaults to writing each
+        /// CSV next to its .msg file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Exports a .msg file, or every .msg file in a directory, as JSON that `pack` can turn back
+    /// into the same .msg file.
+    ExportJson {
+        path: PathBuf,
+
+        /// Output file, or output directory when exporting a directory. Defaults to writing each
+        /// JSON file next to its .msg file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Builds a .msg file from a CSV or JSON export.
+    Pack {
+        /// A file written by export-csv or export-json.
+        input: PathBuf,
+
+        /// The .msg file to write.
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// The original .msg file, which CSV rows are written onto so fields the CSV doesn't have
+        /// are kept.
+        #[arg(long)]
+        base: Option<PathBuf>,
+    },
+    /// Writes a copy of a .msg file with the rows in a JSON or TOML overlay replaced, added or
+    /// deleted.
+    Patch {
+        base: PathBuf,
+
+        /// A JSON or TOML map of id_hash_ to text, optionally with an `add` map and a `delete`
+        /// list of id_hash_.
+        overlay: PathBuf,
+
+        /// The .msg file to write.
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Lists rows added, removed or modified between two .msg files or two text directories.
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+
+        #[arg(short, long, value_enum, default_value_t = DiffFormat::Terminal)]
+        format: DiffFormat,
+    },
+    /// Counts rows, empty text and keys missing from or unique to each language, for every .msg
+    /// file in a text directory.
+    Coverage {
+        #[command(flatten)]
+        text: TextArgs,
+
+        #[arg(short, long, value_enum, default_value_t = CoverageFormat::Terminal)]
+        format: CoverageFormat,
+    },
+    /// Lists rows that look untranslated: text identical to the en or jp row, or written only in
+    /// another language's script.
+    Untranslated {
+        #[command(flatten)]
+        text: TextArgs,
+
+        /// File of proper nouns to ignore, one per line.
+        #[arg(long)]
+        allowlist: Option<PathBuf>,
+    },
+    /// Checks that every language's text has the same tags and placeholders as the other
+    /// languages for each row, exiting with an error if any don't.
+    Lint {
+        #[command(flatten)]
+        text: TextArgs,
+
+        /// Tags to leave out of the comparison, such as ones only some languages use.
+        #[arg(long = "ignore-tag", value_delimiter = ',')]
+        ignore_tags: Vec<String>,
+    },
+    ExtractAll {
+        #[command(flatten)]
+        paths: ExtractPaths,
+
+        /// TOML or JSON file describing the query-based extractors, defaults to the built-in
+        /// extractors.toml.
+        #[arg(long, env = "LANGUAGE_UNPACK_CONFIG")]
+        config: Option<PathBuf>,
+
+        /// Languages to extract, as game codes (jp) or BCP-47 tags (ja). Defaults to every
+        /// language in the text directory.
+        #[arg(long = "lang", value_delimiter = ',')]
+        languages: Vec<Language>,
+
+        /// Write a file per language, a merged file with every language's text, or both.
+        #[arg(long, value_enum, default_value_t = Layout::PerLanguage)]
+        layout: Layout,
+
+        /// Write text without its colour, style and furigana markup. Placeholders are kept.
+        #[arg(long)]
+        plain_text: bool,
+    },
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Cli::parse();
 
     match &args.command {
-        Commands::Print { file } => {
+        Commands::Print { file, names } => {
             let language_file = LanguageFile::open(file.to_str().unwrap())?;
+            let names = names
+                .as_ref()
+                .map(|path| {
+                    HashIndex::open(path).context(format!(
+                        "Could not open hash index at path: {}",
+                        path.display()
+                    ))
+                })
+                .transpose()?;
+
+            for (key, value) in language_file.other_fields() {
+                println!("{}: {}", key.as_str().unwrap_or("?"), value);
+            }
 
             for row in language_file.rows_ {
-                println!("{:?}", row);
+                let resolved = names.as_ref().and_then(|names| {
+                    u32::from_str_radix(&row.column_.id_hash_, 16)
+                        .ok()
+                        .map(|hash| names.get(hash))
+                        .filter(|resolved| !resolved.is_empty())
+                });
+
+                match resolved {
+                    Some(resolved) => println!("{:?} ({})", row, resolved.join(" | ")),
+                    None => println!("{:?}", row),
+                }
             }
         }
-        Commands::ExtractAll {} => {
-            let default_path = "system_table.sqlite";
-            let db = Connection::open(&default_path).context(format!(
+        Commands::ExportCsv { path, output } => {
+            let count = export::export_path(path, output.as_deref(), ExportFormat::Csv)?;
+            eprintln!("Exported {} files", count);
+        }
+        Commands::ExportJson { path, output } => {
+            let count = export::export_path(path, output.as_deref(), ExportFormat::Json)?;
+            eprintln!("Exported {} files", count);
+        }
+        Commands::Pack {
+            input,
+            output,
+            base,
+        } => {
+            let base = base
+                .as_ref()
+                .map(|path| {
+                    LanguageFile::open(&path.to_string_lossy()).context(format!(
+                        "Could not open language file at path: {}",
+                        path.display()
+                    ))
+                })
+                .transpose()?;
+
+            let language_file = match input.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => {
+                    if base.is_some() {
+                        eprintln!("Ignoring --base, JSON exports already have every field");
+                    }
+
+                    pack::read_json(input)?
+                }
+                Some("csv") => pack::read_csv(input, base)?,
+                _ => anyhow::bail!("Expected a .csv or .json file: {}", input.display()),
+            };
+
+            let file = File::create(output).context(format!(
+                "Could not create language file at path: {}",
+                output.display()
+            ))?;
+            language_file.write(std::io::BufWriter::new(file))?;
+
+            eprintln!(
+                "Packed {} rows into {}",
+                language_file.rows_.len(),
+                output.display()
+            );
+        }
+        Commands::Patch {
+            base,
+            overlay,
+            output,
+        } => {
+            let mut language_file = LanguageFile::open(&base.to_string_lossy()).context(
+                format!("Could not open language file at path: {}", base.display()),
+            )?;
+            let overlay = patch::Overlay::open(overlay)?;
+
+            let report = patch::apply(&mut language_file, &overlay);
+            report.print();
+
+            let file = File::create(output).context(format!(
+                "Could not create language file at path: {}",
+                output.display()
+            ))?;
+            language_file.write(std::io::BufWriter::new(file))?;
+        }
+        Commands::Diff { old, new, format } => {
+            let diff = Diff::new(old, new)?;
+            print!("{}", diff.render(*format)?);
+        }
+        Commands::Coverage { text, format } => {
+            // en is always read so other languages' missing keys can be counted.
+            let (languages, tree) = text.open(&[Language::English])?;
+            let report = CoverageReport::new(&tree, &languages);
+
+            match format {
+                CoverageFormat::Terminal => print!("{}", report.to_terminal()),
+                CoverageFormat::Csv => report.write_csv(std::io::stdout().lock())?,
+            }
+        }
+        Commands::Untranslated { text, allowlist } => {
+            let allowlist = match allowlist {
+                Some(path) => Allowlist::open(path)?,
+                None => Allowlist::default(),
+            };
+
+            // en and jp are always read as the text to compare against.
+            let (languages, tree) = text.open(&[Language::English, Language::Japanese])?;
+            let suspects = untranslated::find_suspects(&tree, &languages, &allowlist);
+
+            for suspect in &suspects {
+                let reasons: Vec<String> = suspect
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.to_string())
+                    .collect();
+
+                println!(
+                    "{} {} {}: {}: {:?}",
+                    suspect.language,
+                    suspect.file,
+                    suspect.id_hash_,
+                    reasons.join(", "),
+                    suspect.text
+                );
+            }
+
+            eprintln!("{} suspect rows", suspects.len());
+        }
+        Commands::Lint { text, ignore_tags } => {
+            let (languages, tree) = text.open(&[])?;
+            let mismatches = lint::find_mismatches(&tree, &languages, ignore_tags);
+
+            for mismatch in &mismatches {
+                print!("{}", mismatch.to_terminal());
+            }
+
+            println!("{} rows with mismatched markup", mismatches.len());
+
+            if !mismatches.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Commands::ExtractAll {
+            paths,
+            config,
+            languages,
+            layout,
+            plain_text,
+        } => {
+            let config = match config {
+                Some(path) => ExtractConfig::open(path)?,
+                None => ExtractConfig::default(),
+            };
+
+            let mut msg_files = config.msg_files();
+            msg_files.extend(BUILT_IN_MSG_FILES);
+            msg_files.sort();
+            msg_files.dedup();
+
+            let languages = paths.validate(languages, &msg_files)?;
+
+            let db = Connection::open(&paths.db).context(format!(
                 "Could not open sqlite db at path: {}",
-                &default_path
+                paths.db.display()
             ))?;
+            config.validate(&db)?;
 
-            // Create output data directory if it doesn't exist.
-            for langauge in LANGUAGES {
-                std::fs::create_dir_all(format!("data/{}", langauge)).unwrap();
+            let mut writer = DataWriter::new(paths, *layout, &languages, *plain_text);
+            writer.create_dirs()?;
+
+            Characters::extract(paths, &languages, &mut writer)?;
+            Quests::extract(paths, &languages, &mut writer)?;
+
+            for extractor in &config.extractors {
+                extractor.extract(&db, paths, &languages, &mut writer)?;
             }
 
-            Characters::extract()?;
-            Overmastery::extract(&db)?;
-            Weapon::extract(&db)?;
-            Sigils::extract(&db)?;
-            Traits::extract(&db)?;
-            Items::extract(&db)?;
-            Quests::extract()?;
-            Enemies::extract(&db)?;
+            writer.finish()?;
+
+            let extracted: Vec<String> = languages
+                .iter()
+                .map(|language| format!("{} ({})", language.code(), language.bcp47()))
+                .collect();
+            eprintln!("Extracted {}", extracted.join(", "));
         }
     }
 
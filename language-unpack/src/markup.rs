@@ -0,0 +1,343 @@
+/// A piece of parsed `text_`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Text(String),
+    /// `<name attr=value>children</name>`. The `<name=value>` shorthand is an attribute named
+    /// after the tag, and `<br>` or `<name/>` have no children.
+    Tag {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+    /// `{0}` or `{name}`, without the braces.
+    Placeholder(String),
+    /// `<ruby=reading>base</ruby>` furigana.
+    Ruby {
+        base: String,
+        reading: String,
+    },
+}
+
+/// Tags that never have children or a closing tag.
+const VOID_TAGS: [&str; 1] = ["br"];
+
+type Attrs = Vec<(String, String)>;
+
+enum Token {
+    Text(String),
+    Open {
+        name: String,
+        attrs: Attrs,
+        void: bool,
+    },
+    Close(String),
+    Placeholder(String),
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches(|c| c == '"' || c == '\'').to_string()
+}
+
+/// Parses the inside of `<...>`, splitting attributes on whitespace outside quotes.
+fn parse_tag(inner: &str) -> Token {
+    if let Some(name) = inner.strip_prefix('/') {
+        return Token::Close(name.trim().to_string());
+    }
+
+    let (inner, self_closing) = match inner.strip_suffix('/') {
+        Some(inner) => (inner, true),
+        None => (inner, false),
+    };
+
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut quote = None;
+
+    for c in inner.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                part.push(c);
+            }
+            (Some(open), c) if c == open => {
+                quote = None;
+                part.push(c);
+            }
+            (None, c) if c.is_whitespace() => {
+                if !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+            }
+            _ => part.push(c),
+        }
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    let mut parts = parts.into_iter();
+    let first = parts.next().unwrap_or_default();
+    let (name, mut attrs) = match first.split_once('=') {
+        Some((name, value)) => (name.to_string(), vec![(name.to_string(), unquote(value))]),
+        None => (first, Vec::new()),
+    };
+
+    attrs.extend(parts.map(|part| match part.split_once('=') {
+        Some((key, value)) => (key.to_string(), unquote(value)),
+        None => (part, String::new()),
+    }));
+
+    let void = self_closing || VOID_TAGS.contains(&name.to_ascii_lowercase().as_str());
+
+    Token::Open { name, attrs, void }
+}
+
+/// Markup as written in `text_`, before tags are matched up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Piece<'a> {
+    Text(&'a str),
+    /// `<...>`, including the brackets.
+    Tag(&'a str),
+    /// `{...}`, including the braces.
+    Placeholder(&'a str),
+}
+
+impl<'a> Piece<'a> {
+    /// The name of a tag, without a leading `/` or any attributes.
+    pub fn tag_name(&self) -> Option<&'a str> {
+        match self {
+            Piece::Tag(tag) => tag[1..tag.len() - 1]
+                .trim_start_matches('/')
+                .split(|c: char| c == '=' || c == '/' || c.is_whitespace())
+                .next(),
+            _ => None,
+        }
+    }
+}
+
+/// Splits text into plain text, tags and placeholders. A `<` not followed by a tag name, or a
+/// `<` or `{` without its closing delimiter, is text.
+pub fn pieces(text: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut plain = 0;
+    let mut offset = 0;
+
+    while let Some(c) = text[offset..].chars().next() {
+        let rest = &text[offset..];
+        let end = match c {
+            '<' if rest[1..].starts_with(|next: char| next.is_alphabetic() || next == '/') => {
+                rest.find('>')
+            }
+            '{' => rest.find('}'),
+            _ => None,
+        };
+
+        let Some(end) = end else {
+            offset += c.len_utf8();
+            continue;
+        };
+
+        if plain < offset {
+            pieces.push(Piece::Text(&text[plain..offset]));
+        }
+
+        let markup = &rest[..=end];
+        pieces.push(match c {
+            '<' => Piece::Tag(markup),
+            _ => Piece::Placeholder(markup),
+        });
+
+        offset += end + 1;
+        plain = offset;
+    }
+
+    if plain < text.len() {
+        pieces.push(Piece::Text(&text[plain..]));
+    }
+
+    pieces
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    pieces(text)
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => Token::Text(text.to_string()),
+            Piece::Tag(tag) => parse_tag(&tag[1..tag.len() - 1]),
+            Piece::Placeholder(placeholder) => {
+                Token::Placeholder(placeholder[1..placeholder.len() - 1].to_string())
+            }
+        })
+        .collect()
+}
+
+fn close(name: String, attrs: Attrs, children: Vec<Node>) -> Node {
+    if name.eq_ignore_ascii_case("ruby") {
+        let reading = attrs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("ruby") || key == "reading")
+            .map(|(_, value)| value.clone())
+            .unwrap_or_default();
+
+        return Node::Ruby {
+            base: to_plain(&children),
+            reading,
+        };
+    }
+
+    Node::Tag {
+        name,
+        attrs,
+        children,
+    }
+}
+
+/// Parses markup into nodes. Unclosed tags end with the text, closing tags that don't match an
+/// open tag are dropped, and a `<` or `{` that doesn't start markup is kept as text.
+pub fn parse(text: &str) -> Vec<Node> {
+    let mut open: Vec<(String, Attrs, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+
+    for token in tokenize(text) {
+        let node = match token {
+            Token::Text(text) => Node::Text(text),
+            Token::Placeholder(name) => Node::Placeholder(name),
+            Token::Open { name, attrs, void } if void => Node::Tag {
+                name,
+                attrs,
+                children: Vec::new(),
+            },
+            Token::Open { name, attrs, .. } => {
+                open.push((name, attrs, Vec::new()));
+                continue;
+            }
+            Token::Close(name) => {
+                let Some(index) = open.iter().rposition(|(open, _, _)| *open == name) else {
+                    continue;
+                };
+
+                let mut node = None;
+                while open.len() > index {
+                    let (name, attrs, mut children) = open.pop().unwrap();
+                    children.extend(node);
+                    node = Some(close(name, attrs, children));
+                }
+
+                node.unwrap()
+            }
+        };
+
+        match open.last_mut() {
+            Some((_, _, children)) => children.push(node),
+            None => nodes.push(node),
+        }
+    }
+
+    while let Some((name, attrs, children)) = open.pop() {
+        let node = close(name, attrs, children);
+
+        match open.last_mut() {
+            Some((_, _, parent)) => parent.push(node),
+            None => nodes.push(node),
+        }
+    }
+
+    nodes
+}
+
+impl Node {
+    /// The text a player reads: tags removed, `<br>` as a line break, furigana as its base text
+    /// and placeholders kept for the caller to fill in.
+    pub fn to_plain(&self) -> String {
+        match self {
+            Node::Text(text) => text.clone(),
+            Node::Tag { name, children, .. }
+                if children.is_empty() && name.eq_ignore_ascii_case("br") =>
+            {
+                "\n".to_string()
+            }
+            Node::Tag { children, .. } => to_plain(children),
+            Node::Placeholder(name) => format!("{{{}}}", name),
+            Node::Ruby { base, .. } => base.clone(),
+        }
+    }
+}
+
+pub fn to_plain(nodes: &[Node]) -> String {
+    nodes.iter().map(Node::to_plain).collect()
+}
+
+/// Parses `text` and returns it as plain text.
+pub fn plain_text(text: &str) -> String {
+    to_plain(&parse(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> Node {
+        Node::Text(text.to_string())
+    }
+
+    #[test]
+    fn parses_tags_placeholders_and_ruby() {
+        let nodes =
+            parse("<color=red size=\"2 em\">{0} hot<br></color> <ruby=あつ>熱</ruby>い < 3");
+
+        assert_eq!(
+            nodes,
+            [
+                Node::Tag {
+                    name: "color".to_string(),
+                    attrs: vec![
+                        ("color".to_string(), "red".to_string()),
+                        ("size".to_string(), "2 em".to_string()),
+                    ],
+                    children: vec![
+                        Node::Placeholder("0".to_string()),
+                        text(" hot"),
+                        Node::Tag {
+                            name: "br".to_string(),
+                            attrs: Vec::new(),
+                            children: Vec::new(),
+                        },
+                    ],
+                },
+                text(" "),
+                Node::Ruby {
+                    base: "熱".to_string(),
+                    reading: "あつ".to_string(),
+                },
+                text("い < 3"),
+            ]
+        );
+        assert_eq!(to_plain(&nodes), "{0} hot\n 熱い < 3");
+    }
+
+    #[test]
+    fn recovers_from_unbalanced_tags() {
+        assert_eq!(
+            plain_text("<b>bold <i>both</b> plain</i> {open"),
+            "bold both plain {open"
+        );
+        assert_eq!(plain_text("one<BR>two"), "one\ntwo");
+    }
+
+    #[test]
+    fn splits_pieces() {
+        let pieces = pieces("HP < 50% {0}</color>");
+
+        assert_eq!(
+            pieces,
+            [
+                Piece::Text("HP < 50% "),
+                Piece::Placeholder("{0}"),
+                Piece::Tag("</color>"),
+            ]
+        );
+        assert_eq!(pieces[2].tag_name(), Some("color"));
+        assert_eq!(Piece::Tag("<color=red>").tag_name(), Some("color"));
+    }
+}
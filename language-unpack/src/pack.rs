@@ -0,0 +1,133 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::{bail, Context};
+
+use crate::json::FromJson;
+use crate::language_file::{LanguageFile, LanguageRow, LanguageRowColumn};
+
+/// Reads a file written by `export-json`.
+pub fn read_json(path: &Path) -> anyhow::Result<LanguageFile> {
+    let file = File::open(path).context(format!(
+        "Could not open JSON file at path: {}",
+        path.display()
+    ))?;
+    let FromJson(value) = serde_json::from_reader(BufReader::new(file))
+        .context(format!("Could not parse JSON file: {}", path.display()))?;
+
+    LanguageFile::from_value(value)
+}
+
+/// Reads rows from a file written by `export-csv`. The `id` column is derived from `id_hash_` so
+/// it's ignored.
+///
+/// The CSV only has the text columns, so every other field, and the rest of the file, comes from
+/// the matching row of `base` if given. Rows are matched by `id_hash_` and `sub_id_hash_`, and
+/// rows missing from `base` are added with just the text columns. Base rows the CSV leaves out are
+/// dropped.
+pub fn read_csv(path: &Path, base: Option<LanguageFile>) -> anyhow::Result<LanguageFile> {
+    let mut reader = csv::Reader::from_path(path).context(format!(
+        "Could not open CSV file at path: {}",
+        path.display()
+    ))?;
+
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .context(format!(
+                "CSV is missing the {} column: {}",
+                name,
+                path.display()
+            ))
+    };
+    let id_hash_column = column("id_hash_")?;
+    let sub_id_hash_column = column("sub_id_hash_")?;
+    let text_column = column("text_")?;
+
+    let mut language_file = base.unwrap_or_else(|| LanguageFile::new(Vec::new()));
+    let mut base_rows: HashMap<(String, String), VecDeque<LanguageRow>> = HashMap::new();
+
+    for row in language_file.rows_.drain(..) {
+        let key = (
+            row.column_.id_hash_.clone(),
+            row.column_.sub_id_hash_.clone(),
+        );
+        base_rows.entry(key).or_default().push_back(row);
+    }
+
+    for (line, record) in reader.records().enumerate() {
+        let record = record?;
+        let (Some(id_hash_), Some(sub_id_hash_), Some(text_)) = (
+            record.get(id_hash_column),
+            record.get(sub_id_hash_column),
+            record.get(text_column),
+        ) else {
+            bail!("Row {} of {} is missing columns", line + 2, path.display());
+        };
+
+        let key = (id_hash_.to_string(), sub_id_hash_.to_string());
+        let row = match base_rows.get_mut(&key).and_then(VecDeque::pop_front) {
+            Some(mut row) => {
+                row.column_.text_ = text_.to_string();
+                row
+            }
+            None => LanguageRow::new(LanguageRowColumn::new(id_hash_, sub_id_hash_, text_)),
+        };
+
+        language_file.rows_.push(row);
+    }
+
+    Ok(language_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::write_csv;
+    use rmpv::Value;
+
+    #[test]
+    fn packs_csv_onto_base() {
+        let column = Value::Map(vec![
+            ("id_hash_".into(), "TXT_PL1500".into()),
+            ("sub_id_hash_".into(), "".into()),
+            ("text_".into(), "Vane".into()),
+            ("flag_".into(), 3.into()),
+        ]);
+        let file = Value::Map(vec![
+            ("version_".into(), 2.into()),
+            (
+                "rows_".into(),
+                Value::Array(vec![Value::Map(vec![("column_".into(), column)])]),
+            ),
+        ]);
+        let mut original = Vec::new();
+        rmpv::encode::write_value(&mut original, &file).unwrap();
+        let base = LanguageFile::read(original.as_slice()).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("text.csv");
+        write_csv(&base, File::create(&path).unwrap()).unwrap();
+
+        let mut packed = Vec::new();
+        read_csv(&path, Some(base.clone()))
+            .unwrap()
+            .write(&mut packed)
+            .unwrap();
+        assert_eq!(packed, original);
+
+        std::fs::write(
+            &path,
+            "id,id_hash_,sub_id_hash_,text_\n,TXT_PL1500,,Lancelot\n,TXT_PL1600,,Percival\n",
+        )
+        .unwrap();
+        let edited = read_csv(&path, Some(base)).unwrap();
+        assert_eq!(edited.rows_[0].column_.text_, "Lancelot");
+        assert_eq!(edited.rows_[0].column_.other_fields().count(), 1);
+        assert_eq!(edited.rows_[1].column_.id_hash_, "TXT_PL1600");
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use xxhash32_lib::xxhash32_custom;
+
+use crate::language_file::{LanguageFile, LanguageRow, LanguageRowColumn};
+
+/// A small set of text changes to apply onto a base .msg file, read from JSON or TOML.
+///
+/// Usually a flat map of rows to replace:
+///
+/// ```toml
+/// TXT_PL1500 = "Vane"
+/// 48110ba3 = "Ascalon"
+/// ```
+///
+/// Rows can also be added or deleted with the optional `add` and `delete` sections. Ids named
+/// `replace`, `add` or `delete` must go in a `[replace]` section, as they'd be read as sections:
+///
+/// ```toml
+/// delete = ["TXT_UNUSED"]
+///
+/// [add]
+/// TXT_MOD_0001 = "New text"
+/// ```
+///
+/// Keys are either `id_hash_` or the xxhash32 of it as hex, resolved the same way as the
+/// extractors resolve translation ids.
+#[derive(Debug, Default, Deserialize)]
+#[serde(try_from = "BTreeMap<String, OverlayValue>")]
+pub struct Overlay {
+    pub replace: BTreeMap<String, String>,
+    pub add: BTreeMap<String, String>,
+    pub delete: Vec<String>,
+}
+
+/// A top-level overlay value, either a flat row's text or a section.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OverlayValue {
+    Text(String),
+    Texts(BTreeMap<String, String>),
+    Keys(Vec<String>),
+}
+
+impl TryFrom<BTreeMap<String, OverlayValue>> for Overlay {
+    type Error = String;
+
+    fn try_from(values: BTreeMap<String, OverlayValue>) -> Result<Self, Self::Error> {
+        let mut overlay = Overlay::default();
+        let mut texts = BTreeMap::new();
+
+        for (key, value) in values {
+            match (key.as_str(), value) {
+                ("replace" | "add" | "delete", OverlayValue::Text(_)) => {
+                    return Err(format!(
+                        "{} is a section name, put a row with that id in a [replace] section",
+                        key
+                    ))
+                }
+                ("replace", OverlayValue::Texts(replace)) => overlay.replace = replace,
+                ("add", OverlayValue::Texts(add)) => overlay.add = add,
+                ("delete", OverlayValue::Keys(delete)) => overlay.delete = delete,
+                (_, OverlayValue::Text(text)) => {
+                    texts.insert(key, text);
+                }
+                ("replace" | "add", _) => return Err(format!("Expected {} to be a map", key)),
+                ("delete", _) => return Err("Expected delete to be a list of ids".to_string()),
+                _ => return Err(format!("Expected the text for {} to be a string", key)),
+            }
+        }
+
+        // Top-level entries are the same as `replace`.
+        texts.append(&mut overlay.replace);
+        overlay.replace = texts;
+
+        Ok(overlay)
+    }
+}
+
+impl Overlay {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path).context(format!(
+            "Could not read overlay at path: {}",
+            path.display()
+        ))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&contents)
+                .context(format!("Could not parse overlay: {}", path.display())),
+            Some("toml") => toml::from_str(&contents)
+                .context(format!("Could not parse overlay: {}", path.display())),
+            _ => bail!("Expected a .json or .toml overlay: {}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PatchReport {
+    pub replaced: usize,
+    pub added: usize,
+    pub deleted: usize,
+    /// Keys to replace or delete that aren't in the base, each listed once.
+    pub missing: Vec<String>,
+    /// Keys to add that are already in the base.
+    pub existing: Vec<String>,
+}
+
+impl PatchReport {
+    pub fn print(&self) {
+        for key in &self.missing {
+            eprintln!("Not in base: {}", key);
+        }
+
+        for key in &self.existing {
+            eprintln!("Already in base, not added: {}", key);
+        }
+
+        eprintln!(
+            "Replaced {}, added {}, deleted {} rows, {} keys not in base, {} already in base",
+            self.replaced,
+            self.added,
+            self.deleted,
+            self.missing.len(),
+            self.existing.len()
+        );
+    }
+}
+
+/// Rows by `id_hash_` and by its xxhash32, so looking up a key doesn't hash every row.
+#[derive(Default)]
+struct RowIndex {
+    hashes: HashMap<u32, Vec<usize>>,
+    ids: HashMap<String, Vec<usize>>,
+}
+
+impl RowIndex {
+    fn new(language_file: &LanguageFile) -> Self {
+        let mut index = RowIndex::default();
+
+        for (i, row) in language_file.rows_.iter().enumerate() {
+            index.insert(i, &row.column_.id_hash_);
+        }
+
+        index
+    }
+
+    fn insert(&mut self, i: usize, id_hash_: &str) {
+        let hash = xxhash32_custom(id_hash_.as_bytes());
+        self.hashes.entry(hash).or_default().push(i);
+        self.ids.entry(id_hash_.to_string()).or_default().push(i);
+    }
+
+    /// Indexes of every row `key` refers to, preferring rows whose hashed `id_hash_` matches.
+    fn find(&self, key: &str) -> &[usize] {
+        let hashed = u32::from_str_radix(key, 16)
+            .ok()
+            .and_then(|hash| self.hashes.get(&hash));
+
+        hashed
+            .or_else(|| self.ids.get(key))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Applies deletions, then replacements, then additions. Added rows get the same fields as the
+/// base's first row, so every row in the file has the same shape.
+pub fn apply(language_file: &mut LanguageFile, overlay: &Overlay) -> PatchReport {
+    let mut report = PatchReport::default();
+    let template = language_file.rows_.first().cloned();
+    let index = RowIndex::new(language_file);
+
+    // Checked against the base before anything is deleted, so a key that's both deleted and
+    // replaced isn't reported as missing.
+    for key in overlay.delete.iter().chain(overlay.replace.keys()) {
+        if !report.missing.contains(key) && index.find(key).is_empty() {
+            report.missing.push(key.clone());
+        }
+    }
+
+    let deleted: HashSet<usize> = overlay
+        .delete
+        .iter()
+        .flat_map(|key| index.find(key))
+        .copied()
+        .collect();
+
+    if !deleted.is_empty() {
+        let mut i = 0;
+        language_file.rows_.retain(|_| {
+            i += 1;
+            !deleted.contains(&(i - 1))
+        });
+        report.deleted = deleted.len();
+    }
+
+    let mut index = RowIndex::new(language_file);
+
+    for (key, text) in &overlay.replace {
+        let rows = index.find(key);
+
+        for &i in rows {
+            language_file.rows_[i].column_.text_ = text.clone();
+        }
+
+        report.replaced += rows.len();
+    }
+
+    for (key, text) in &overlay.add {
+        if !index.find(key).is_empty() {
+            report.existing.push(key.clone());
+            continue;
+        }
+
+        let row = match &template {
+            Some(template) => LanguageRow::like(template, key, text),
+            None => LanguageRow::new(LanguageRowColumn::new(key, "", text)),
+        };
+
+        index.insert(language_file.rows_.len(), key);
+        language_file.rows_.push(row);
+        report.added += 1;
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_overlay() {
+        let mut language_file = LanguageFile::new(vec![
+            LanguageRow::new(LanguageRowColumn::new("TXT_PL1500", "", "Vane")),
+            LanguageRow::new(LanguageRowColumn::new("WEP_PL1500_04_03", "", "Ascalon")),
+            LanguageRow::new(LanguageRowColumn::new("TXT_UNUSED", "", "")),
+        ]);
+
+        let overlay: Overlay = toml::from_str(
+            r#"
+            delete = ["TXT_UNUSED", "TXT_GONE"]
+
+            [replace]
+            TXT_PL1500 = "Lancelot"
+            48110ba3 = "Blutgang"
+            TXT_GONE = "Gone"
+
+            [add]
+            TXT_MOD_0001 = "New"
+            TXT_PL1500 = "Duplicate"
+            "#,
+        )
+        .unwrap();
+
+        let report = apply(&mut language_file, &overlay);
+
+        let texts: Vec<&str> = language_file
+            .rows_
+            .iter()
+            .map(|row| row.column_.text_.as_str())
+            .collect();
+        assert_eq!(texts, vec!["Lancelot", "Blutgang", "New"]);
+        assert_eq!(report.missing, vec!["TXT_GONE"]);
+        assert_eq!(report.existing, vec!["TXT_PL1500"]);
+    }
+
+    #[test]
+    fn applies_flat_overlay() {
+        let mut language_file = LanguageFile::new(vec![
+            LanguageRow::new(LanguageRowColumn::new("TXT_PL1500", "", "Vane")),
+            LanguageRow::new(LanguageRowColumn::new("WEP_PL1500_04_03", "", "Ascalon")),
+        ]);
+
+        let overlay: Overlay =
+            serde_json::from_str(r#"{"TXT_PL1500": "Lancelot", "48110ba3": "Blutgang"}"#).unwrap();
+
+        let report = apply(&mut language_file, &overlay);
+
+        let texts: Vec<&str> = language_file
+            .rows_
+            .iter()
+            .map(|row| row.column_.text_.as_str())
+            .collect();
+        assert_eq!(texts, vec!["Lancelot", "Blutgang"]);
+        assert_eq!(report.replaced, 2);
+        assert!(report.missing.is_empty());
+
+        assert!(serde_json::from_str::<Overlay>(r#"{"add": "Text"}"#).is_err());
+        let overlay: Overlay = serde_json::from_str(r#"{"replace": {"add": "Text"}}"#).unwrap();
+        assert_eq!(overlay.replace["add"], "Text");
+    }
+
+    #[test]
+    fn adds_rows_shaped_like_the_base() {
+        let column = rmpv::Value::Map(vec![
+            ("text_".into(), "Vane".into()),
+            ("flag_".into(), 3.into()),
+            ("sub_id_hash_".into(), 7.into()),
+            ("id_hash_".into(), "TXT_PL1500".into()),
+        ]);
+        let row = rmpv::Value::Map(vec![
+            ("column_".into(), column),
+            ("index_".into(), 4.into()),
+        ]);
+        let file = rmpv::Value::Map(vec![("rows_".into(), rmpv::Value::Array(vec![row]))]);
+        let mut language_file = LanguageFile::from_value(file).unwrap();
+
+        let overlay: Overlay = toml::from_str("[add]\nTXT_MOD_0001 = \"New\"").unwrap();
+        apply(&mut language_file, &overlay);
+
+        let value = language_file.to_value().unwrap();
+        let rows = value.as_map().unwrap()[0].1.as_array().unwrap();
+        assert_eq!(
+            rows[1],
+            rmpv::Value::Map(vec![
+                (
+                    "column_".into(),
+                    rmpv::Value::Map(vec![
+                        ("text_".into(), "New".into()),
+                        ("flag_".into(), 0.into()),
+                        ("sub_id_hash_".into(), 0.into()),
+                        ("id_hash_".into(), "TXT_MOD_0001".into()),
+                    ])
+                ),
+                ("index_".into(), 0.into()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+
+use anyhow::bail;
+use clap::Args;
+
+use crate::language::Language;
+use crate::texts::TextTree;
+
+/// The text directory and languages read by `coverage`, `untranslated` and `lint`.
+#[derive(Debug, Clone, Args)]
+pub struct TextArgs {
+    /// Directory holding each language's .msg files in a {lang}/ subdirectory, e.g. text/en/.
+    #[arg(env = "LANGUAGE_UNPACK_TEXT_DIR", default_value = "text")]
+    pub text_dir: PathBuf,
+
+    /// Languages to read, as game codes (jp) or BCP-47 tags (ja). Defaults to every language in
+    /// the text directory.
+    #[arg(long = "lang", value_delimiter = ',')]
+    pub languages: Vec<Language>,
+}
+
+impl TextArgs {
+    /// Opens the selected languages, along with any of `compare_with` the text directory has.
+    /// Returns the selected languages and the tree.
+    pub fn open(&self, compare_with: &[Language]) -> anyhow::Result<(Vec<Language>, TextTree)> {
+        let discovered = Language::discover(&self.text_dir)?;
+        let languages = if self.languages.is_empty() {
+            discovered.clone()
+        } else {
+            self.languages.clone()
+        };
+
+        let mut read = languages.clone();
+        read.extend(
+            compare_with
+                .iter()
+                .filter(|language| discovered.contains(language)),
+        );
+        read.sort();
+        read.dedup();
+
+        Ok((languages, TextTree::open(&self.text_dir, &read)?))
+    }
+}
+
+/// Where `extract-all` reads the game files from and writes the data files to.
+#[derive(Debug, Clone, Args)]
+pub struct ExtractPaths {
+    /// The game's system_table.sqlite.
+    #[arg(
+        long,
+        env = "LANGUAGE_UNPACK_DB",
+        default_value = "system_table.sqlite"
+    )]
+    pub db: PathBuf,
+
+    /// Directory holding each language's .msg files in a {lang}/ subdirectory, e.g. text/en/.
+    #[arg(long, env = "LANGUAGE_UNPACK_TEXT_DIR", default_value = "text")]
+    pub text_dir: PathBuf,
+
+    /// Directory the data/{lang}/*.json files are written to.
+    #[arg(long, env = "LANGUAGE_UNPACK_OUTPUT_DIR", default_value = "data")]
+    pub output_dir: PathBuf,
+
+    /// Name languages by BCP-47 tag (data/ja/) instead of game code (data/jp/), in directories and
+    /// merged files.
+    #[arg(long)]
+    pub bcp47_output: bool,
+}
+
+impl ExtractPaths {
+    pub fn text(&self, language: Language, file_name: &str) -> PathBuf {
+        self.text_dir.join(language.code()).join(file_name)
+    }
+
+    /// How a language is named in the output.
+    pub fn language_name(&self, language: Language) -> &'static str {
+        if self.bcp47_output {
+            language.bcp47()
+        } else {
+            language.code()
+        }
+    }
+
+    pub fn language_output_dir(&self, language: Language) -> PathBuf {
+        self.output_dir.join(self.language_name(language))
+    }
+
+    pub fn output(&self, language: Language, file_name: &str) -> PathBuf {
+        self.language_output_dir(language).join(file_name)
+    }
+
+    /// Checks that the database, the text directory and every language's .msg files exist,
+    /// listing everything missing at once rather than failing part way through extraction.
+    ///
+    /// Returns the languages to extract, which default to every language in the text directory.
+    pub fn validate(
+        &self,
+        languages: &[Language],
+        msg_files: &[&str],
+    ) -> anyhow::Result<Vec<Language>> {
+        let mut missing = Vec::new();
+
+        if !self.db.is_file() {
+            missing.push(self.db.display().to_string());
+        }
+
+        let languages = if !languages.is_empty() {
+            languages.to_vec()
+        } else if self.text_dir.is_dir() {
+            let discovered = Language::discover(&self.text_dir)?;
+
+            if discovered.is_empty() {
+                missing.push(format!(
+                    "{} (no language directories found)",
+                    self.text_dir.join("{lang}").display()
+                ));
+            }
+
+            discovered
+        } else {
+            missing.push(self.text_dir.display().to_string());
+            Vec::new()
+        };
+
+        for language in &languages {
+            for file_name in msg_files {
+                let path = self.text(*language, file_name);
+
+                if !path.is_file() {
+                    missing.push(path.display().to_string());
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(languages);
+        }
+
+        let list: Vec<String> = missing.iter().map(|path| format!("  {}", path)).collect();
+
+        bail!(
+            "Missing {} paths, set --db, --text-dir or their environment variables:\n{}",
+            missing.len(),
+            list.join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_missing_file() {
+        let paths = ExtractPaths {
+            db: PathBuf::from("missing/system_table.sqlite"),
+            text_dir: PathBuf::from("missing/text"),
+            output_dir: PathBuf::from("data"),
+            bcp47_output: true,
+        };
+
+        assert_eq!(
+            paths.output(Language::Japanese, "items.json"),
+            PathBuf::from("data/ja/items.json")
+        );
+
+        let error = paths
+            .validate(
+                &[Language::English, Language::Japanese],
+                &["text.msg", "text_chara.msg"],
+            )
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.starts_with("Missing 5 paths"));
+        assert!(error.contains(
+            &PathBuf::from("missing/text/jp/text_chara.msg")
+                .display()
+                .to_string()
+        ));
+
+        let error = paths.validate(&[], &["text.msg"]).unwrap_err().to_string();
+        assert!(error.starts_with("Missing 2 paths"));
+        assert!(error.contains(&paths.db.display().to_string()));
+        assert!(error.contains(&paths.text_dir.display().to_string()));
+    }
+}
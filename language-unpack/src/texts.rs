@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::export::find_msg_files;
+use crate::language::Language;
+use crate::language_file::LanguageFile;
+
+/// Every language's copy of each .msg file in a text/{lang}/ directory.
+pub struct TextTree {
+    /// Language files by path relative to the language directory, then by language. A language
+    /// without a copy of the file has no entry.
+    pub files: BTreeMap<String, BTreeMap<Language, LanguageFile>>,
+}
+
+impl TextTree {
+    pub fn open(text_dir: &Path, languages: &[Language]) -> anyhow::Result<Self> {
+        let mut files: BTreeMap<String, BTreeMap<Language, LanguageFile>> = BTreeMap::new();
+
+        for &language in languages {
+            let language_dir = text_dir.join(language.code());
+
+            for path in find_msg_files(&language_dir)? {
+                let name = path
+                    .strip_prefix(&language_dir)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let language_file = LanguageFile::open(&path.to_string_lossy()).context(
+                    format!("Could not open language file at path: {}", path.display()),
+                )?;
+
+                files
+                    .entry(name)
+                    .or_default()
+                    .insert(language, language_file);
+            }
+        }
+
+        Ok(TextTree { files })
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::language::Language;
+use crate::language_file::LanguageFile;
+use crate::markup::{self, Node};
+use crate::texts::TextTree;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Kana,
+    Han,
+    Hangul,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Script> {
+        match c {
+            '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+                Some(Script::Kana)
+            }
+            '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{20000}'..='\u{2FFFF}' => Some(Script::Han),
+            '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+                Some(Script::Hangul)
+            }
+            c if c.is_alphabetic()
+                && (c <= '\u{024F}' || ('\u{FF21}'..='\u{FF5A}').contains(&c)) =>
+            {
+                Some(Script::Latin)
+            }
+            _ => None,
+        }
+    }
+
+    /// The scripts a language's text is expected to use at least one of.
+    fn expected(language: Language) -> &'static [Script] {
+        match language {
+            Language::SimplifiedChinese | Language::TraditionalChinese => &[Script::Han],
+            Language::Japanese => &[Script::Kana, Script::Han],
+            Language::Korean => &[Script::Hangul],
+            _ => &[Script::Latin],
+        }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Script::Latin => "Latin",
+            Script::Kana => "kana",
+            Script::Han => "Han",
+            Script::Hangul => "Hangul",
+        })
+    }
+}
+
+/// The text of `nodes` without tags, placeholders or furigana, which are never translated.
+fn strip_markup(nodes: &[Node]) -> String {
+    let mut plain = String::new();
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => plain.push_str(text),
+            Node::Tag { children, .. } => {
+                plain.push(' ');
+                plain.push_str(&strip_markup(children));
+            }
+            Node::Placeholder(_) => plain.push(' '),
+            Node::Ruby { base, .. } => plain.push_str(base),
+        }
+    }
+
+    plain
+}
+
+/// Replaces whole-word occurrences of `term` with a space. A match only counts as a word if the
+/// letters around it are in a different script, so "Io" matches in "Io" and "Io의" but not "Ion".
+fn remove_term(text: &str, term: &str) -> String {
+    let continues = |edge: Option<char>, next: Option<char>| match (edge, next) {
+        (Some(edge), Some(next)) => next.is_alphanumeric() && Script::of(next) == Script::of(edge),
+        _ => false,
+    };
+
+    let mut output = String::new();
+    let mut last = 0;
+
+    for (start, _) in text.match_indices(term) {
+        let end = start + term.len();
+
+        if start < last
+            || continues(term.chars().next(), text[..start].chars().next_back())
+            || continues(term.chars().next_back(), text[end..].chars().next())
+        {
+            continue;
+        }
+
+        output.push_str(&text[last..start]);
+        output.push(' ');
+        last = end;
+    }
+
+    output.push_str(&text[last..]);
+    output
+}
+
+/// Proper nouns that are expected to be the same in every language, one per line, with `#`
+/// comments.
+#[derive(Debug, Default)]
+pub struct Allowlist {
+    terms: Vec<String>,
+}
+
+impl Allowlist {
+    pub fn new(terms: impl IntoIterator<Item = String>) -> Self {
+        let mut terms: Vec<String> = terms.into_iter().collect();
+        // Longest first, so a term containing another is removed whole.
+        terms.sort_by_key(|term| std::cmp::Reverse(term.chars().count()));
+
+        Allowlist { terms }
+    }
+
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path).context(format!(
+            "Could not read allowlist at path: {}",
+            path.display()
+        ))?;
+
+        Ok(Allowlist::new(
+            text.lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        ))
+    }
+
+    /// The text with markup and allowlisted words removed, leaving what should be translated.
+    fn translatable(&self, text: &str) -> String {
+        let mut text = strip_markup(&markup::parse(text));
+
+        for term in &self.terms {
+            text = remove_term(&text, term);
+        }
+
+        text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    SameAs(Language),
+    /// None of the letters are in a script the language uses.
+    WrongScript(Script),
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::SameAs(language) => write!(f, "same as {}", language),
+            Reason::WrongScript(script) => write!(f, "only {} letters", script),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suspect {
+    pub file: String,
+    pub language: Language,
+    pub id_hash_: String,
+    pub text: String,
+    pub reasons: Vec<Reason>,
+}
+
+fn texts(language_file: &LanguageFile) -> HashMap<&str, &str> {
+    let mut texts = HashMap::new();
+
+    for row in &language_file.rows_ {
+        texts
+            .entry(row.column_.id_hash_.as_str())
+            .or_insert(row.column_.text_.as_str());
+    }
+
+    texts
+}
+
+/// Finds rows in `languages` that look untranslated: text identical to the `en` or `jp` row with
+/// the same `id_hash_`, or text written only in another language's script.
+pub fn find_suspects(
+    tree: &TextTree,
+    languages: &[Language],
+    allowlist: &Allowlist,
+) -> Vec<Suspect> {
+    let sources = [Language::English, Language::Japanese];
+    let mut suspects = Vec::new();
+
+    for (file, copies) in &tree.files {
+        let source_texts: BTreeMap<Language, HashMap<&str, &str>> = sources
+            .iter()
+            .filter_map(|source| Some((*source, texts(copies.get(source)?))))
+            .collect();
+
+        for &language in languages {
+            let Some(language_file) = copies.get(&language) else {
+                continue;
+            };
+
+            for row in &language_file.rows_ {
+                let column = &row.column_;
+                let translatable = allowlist.translatable(&column.text_);
+                let scripts: Vec<Option<Script>> = translatable
+                    .chars()
+                    .filter(|c| c.is_alphabetic())
+                    .map(Script::of)
+                    .collect();
+
+                if scripts.is_empty() {
+                    continue;
+                }
+
+                let mut reasons: Vec<Reason> = source_texts
+                    .iter()
+                    .filter(|(source, texts)| {
+                        **source != language
+                            && texts.get(column.id_hash_.as_str()) == Some(&column.text_.as_str())
+                    })
+                    .map(|(source, _)| Reason::SameAs(*source))
+                    .collect();
+
+                let expected = Script::expected(language);
+                if !scripts
+                    .iter()
+                    .any(|script| script.is_some_and(|script| expected.contains(&script)))
+                {
+                    // Report the script most of the letters are in.
+                    let mut counts: Vec<(Script, usize)> = Vec::new();
+                    for script in scripts.into_iter().flatten() {
+                        match counts.iter_mut().find(|(counted, _)| *counted == script) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((script, 1)),
+                        }
+                    }
+
+                    if let Some((script, _)) = counts.iter().max_by_key(|(_, count)| *count) {
+                        reasons.push(Reason::WrongScript(*script));
+                    }
+                }
+
+                if !reasons.is_empty() {
+                    suspects.push(Suspect {
+                        file: file.clone(),
+                        language,
+                        id_hash_: column.id_hash_.clone(),
+                        text: column.text_.clone(),
+                        reasons,
+                    });
+                }
+            }
+        }
+    }
+
+    suspects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_identical_and_wrong_script_text() {
+        let mut copies = BTreeMap::new();
+        copies.insert(
+            Language::English,
+            LanguageFile::from_texts(&[
+                ("TXT_A", "Sword"),
+                ("TXT_B", "Ascalon"),
+                ("TXT_C", "<color=red>Hot</color>"),
+                ("TXT_D", "100"),
+                ("TXT_F", "HP < 50% left"),
+                ("TXT_G", "Ion"),
+            ]),
+        );
+        copies.insert(
+            Language::Japanese,
+            LanguageFile::from_texts(&[("TXT_A", "剣"), ("TXT_B", "アスカロン")]),
+        );
+        copies.insert(
+            Language::Korean,
+            LanguageFile::from_texts(&[
+                ("TXT_A", "Sword"),
+                ("TXT_B", "Ascalon"),
+                ("TXT_C", "<color=red>뜨거운</color>"),
+                ("TXT_D", "100"),
+                ("TXT_E", "ソード"),
+                ("TXT_F", "HP < 50% left"),
+                ("TXT_G", "Ion"),
+                ("TXT_H", "Ascalon의 {0}"),
+            ]),
+        );
+
+        let mut tree = TextTree {
+            files: BTreeMap::new(),
+        };
+        tree.files.insert("text.msg".to_string(), copies);
+
+        let allowlist = Allowlist::new(["Ascalon".to_string(), "Ion".to_string()]);
+        let suspects = find_suspects(&tree, &[Language::Korean, Language::Japanese], &allowlist);
+        let found: Vec<(&str, String)> = suspects
+            .iter()
+            .map(|suspect| {
+                let reasons: Vec<String> = suspect.reasons.iter().map(Reason::to_string).collect();
+                (suspect.id_hash_.as_str(), reasons.join(", "))
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                ("TXT_A", "same as en, only Latin letters".to_string()),
+                ("TXT_E", "only kana letters".to_string()),
+                ("TXT_F", "same as en, only Latin letters".to_string()),
+            ]
+        );
+
+        let allowlist = Allowlist::new(["Io".to_string()]);
+        assert_eq!(allowlist.translatable("Io, Ion<br>"), " , Ion ");
+    }
+}
@@ -0,0 +1,241 @@
+//! Compact, memory-mapped hash to name index.
+//!
+//! Loading multi-million entry `hash,value` CSVs into a `HashMap` is slow and memory-hungry, so
+//! dictionaries can be built into an index file once and memory-mapped for lookups.
+//!
+//! Layout, all integers little-endian:
+//!
+//! ```text
+//! header        magic "XXH32IDX", version: u32, hash_count: u32, value_count: u32, pool_size: u32, 8 reserved bytes
+//! hashes        [u32; hash_count], sorted
+//! value_starts  [u32; hash_count + 1], hash i has values value_starts[i]..value_starts[i + 1]
+//! values        [(offset: u32, length: u32); value_count], slices of the string pool
+//! pool          [u8; pool_size], UTF-8 names
+//! ```
+//!
+//! xxhash32 collides a lot, so every hash can have several names via `value_starts`.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+const MAGIC: &[u8; 8] = b"XXH32IDX";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 32;
+
+/// Collects names and writes them out as an index file.
+#[derive(Debug, Default)]
+pub struct HashIndexBuilder {
+    names: BTreeMap<u32, Vec<String>>,
+}
+
+impl HashIndexBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a name for a hash, ignoring duplicates.
+    pub fn insert(&mut self, hash: u32, name: &str) {
+        let names = self.names.entry(hash).or_default();
+
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn write<W: Write>(&self, mut output: W) -> io::Result<()> {
+        let value_count: usize = self.names.values().map(Vec::len).sum();
+        let pool_size: usize = self.names.values().flatten().map(String::len).sum();
+
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "index is too large");
+        let hash_count = u32::try_from(self.names.len()).map_err(|_| too_large())?;
+        let value_count = u32::try_from(value_count).map_err(|_| too_large())?;
+        let pool_size = u32::try_from(pool_size).map_err(|_| too_large())?;
+
+        output.write_all(MAGIC)?;
+        output.write_all(&VERSION.to_le_bytes())?;
+        output.write_all(&hash_count.to_le_bytes())?;
+        output.write_all(&value_count.to_le_bytes())?;
+        output.write_all(&pool_size.to_le_bytes())?;
+        output.write_all(&[0; 8])?;
+
+        for hash in self.names.keys() {
+            output.write_all(&hash.to_le_bytes())?;
+        }
+
+        let mut value_start = 0u32;
+        output.write_all(&value_start.to_le_bytes())?;
+        for names in self.names.values() {
+            value_start += names.len() as u32;
+            output.write_all(&value_start.to_le_bytes())?;
+        }
+
+        let mut pool_offset = 0u32;
+        for name in self.names.values().flatten() {
+            output.write_all(&pool_offset.to_le_bytes())?;
+            output.write_all(&(name.len() as u32).to_le_bytes())?;
+            pool_offset += name.len() as u32;
+        }
+
+        for name in self.names.values().flatten() {
+            output.write_all(name.as_bytes())?;
+        }
+
+        output.flush()
+    }
+}
+
+/// A memory-mapped index file.
+#[derive(Debug)]
+pub struct HashIndex {
+    mmap: Mmap,
+    hash_count: usize,
+    value_count: usize,
+}
+
+impl HashIndex {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        // SAFETY: The index is only ever read, and every access is bounds checked against the
+        // sizes validated below. Modifying the file while it's open is not supported.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        if mmap.len() < HEADER_SIZE || &mmap[0..8] != MAGIC {
+            return Err(invalid("not a hash index file"));
+        }
+
+        let read = |offset: usize| u32::from_le_bytes(mmap[offset..offset + 4].try_into().unwrap());
+
+        if read(8) != VERSION {
+            return Err(invalid("unsupported hash index version"));
+        }
+
+        let hash_count = read(12) as usize;
+        let value_count = read(16) as usize;
+        let pool_size = read(20) as usize;
+        let expected_size =
+            HEADER_SIZE + hash_count * 4 + (hash_count + 1) * 4 + value_count * 8 + pool_size;
+
+        if mmap.len() != expected_size {
+            return Err(invalid("hash index file is truncated"));
+        }
+
+        Ok(Self {
+            mmap,
+            hash_count,
+            value_count,
+        })
+    }
+
+    /// Number of unique hashes in the index.
+    pub fn len(&self) -> usize {
+        self.hash_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hash_count == 0
+    }
+
+    fn read_u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.mmap[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn hash_at(&self, i: usize) -> u32 {
+        self.read_u32(HEADER_SIZE + i * 4)
+    }
+
+    /// Every hash in the index, in ascending order.
+    pub fn hashes(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.hash_count).map(|i| self.hash_at(i))
+    }
+
+    /// Every hash with its names, in ascending hash order.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Vec<&str>)> + '_ {
+        (0..self.hash_count).map(|i| (self.hash_at(i), self.names_at(i)))
+    }
+
+    /// Every name for a hash, empty if the hash isn't in the index.
+    pub fn get(&self, hash: u32) -> Vec<&str> {
+        let (mut low, mut high) = (0, self.hash_count);
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+
+            match self.hash_at(middle).cmp(&hash) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return self.names_at(middle),
+            }
+        }
+
+        Vec::new()
+    }
+
+    fn names_at(&self, i: usize) -> Vec<&str> {
+        let value_starts = HEADER_SIZE + self.hash_count * 4;
+        let values = value_starts + (self.hash_count + 1) * 4;
+        let pool = values + self.value_count * 8;
+
+        let start = self.read_u32(value_starts + i * 4) as usize;
+        let end = self.read_u32(value_starts + (i + 1) * 4) as usize;
+
+        (start..end.min(self.value_count))
+            .filter_map(|value| {
+                let offset = self.read_u32(values + value * 8) as usize;
+                let length = self.read_u32(values + value * 8 + 4) as usize;
+                let bytes = self.mmap.get(pool + offset..pool + offset + length)?;
+                std::str::from_utf8(bytes).ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_names() {
+        let mut builder = HashIndexBuilder::new();
+        builder.insert(0x48110BA3, "WEP_PL1500_04_03");
+        builder.insert(0x9AD6310D, "hello");
+        builder.insert(0x9AD6310D, "collision");
+        builder.insert(0x9AD6310D, "hello");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.idx");
+        builder.write(File::create(&path).unwrap()).unwrap();
+
+        let index = HashIndex::open(&path).unwrap();
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.get(0x48110BA3), vec!["WEP_PL1500_04_03"]);
+        assert_eq!(index.get(0x9AD6310D), vec!["hello", "collision"]);
+        assert!(index.get(0x887AE0B0).is_empty());
+        assert_eq!(
+            index.hashes().collect::<Vec<_>>(),
+            vec![0x48110BA3, 0x9AD6310D]
+        );
+        assert_eq!(
+            index.iter().collect::<Vec<_>>(),
+            vec![
+                (0x48110BA3, vec!["WEP_PL1500_04_03"]),
+                (0x9AD6310D, vec!["hello", "collision"]),
+            ]
+        );
+    }
+}
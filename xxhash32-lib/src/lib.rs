@@ -1,3 +1,5 @@
+pub mod index;
+
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::io::Cursor;
 
@@ -13,7 +15,7 @@ const PRIME32_5: u32 = 0x165667B1;
 
 #[inline(always)]
 fn xxhash32_rotl(x: u32, r: u32) -> u32 {
-    (x << r) | (x >> (32 - r))
+    x.rotate_left(r)
 }
 
 #[inline(always)]
@@ -0,0 +1,469 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::dictionary::{parse_hash, Dictionary};
+
+/// Formats `annotate` understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AnnotateFormat {
+    Json,
+    Csv,
+    Tsv,
+    Text,
+}
+
+impl AnnotateFormat {
+    /// Infers the format from a file extension, treating anything unknown as text.
+    pub fn from_extension(extension: Option<&str>) -> Self {
+        match extension {
+            Some("json") => AnnotateFormat::Json,
+            Some("csv") => AnnotateFormat::Csv,
+            Some("tsv") => AnnotateFormat::Tsv,
+            _ => AnnotateFormat::Text,
+        }
+    }
+}
+
+/// A JSON document that keeps object fields in their original order, so annotated output can be
+/// diffed against its input. Strings, numbers, bools and null are kept as plain `Value`s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Object(Vec<(String, Json)>),
+    Array(Vec<Json>),
+    Scalar(Value),
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Json::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            Json::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Json::Scalar(value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::Bool(value)))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::from(value)))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::from(value)))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::from(value)))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::String(value.to_string())))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::String(value)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Json, E> {
+        Ok(Json::Scalar(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Json::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json, A::Error> {
+        let mut fields = Vec::new();
+        while let Some(field) = map.next_entry()? {
+            fields.push(field);
+        }
+        Ok(Json::Object(fields))
+    }
+}
+
+/// Resolves hashes found in other tools' dumps.
+pub struct Annotator<'a> {
+    dictionary: &'a Dictionary,
+    /// Replace hashes with their names instead of adding a `_name` sibling.
+    pub replace: bool,
+    /// Also match decimal u32 numbers, not just 8-digit hex tokens. Off by default, as counts and
+    /// other small integers would be annotated too.
+    pub numbers: bool,
+    pub resolved: usize,
+}
+
+impl<'a> Annotator<'a> {
+    pub fn new(dictionary: &'a Dictionary) -> Self {
+        Self {
+            dictionary,
+            replace: false,
+            numbers: false,
+            resolved: 0,
+        }
+    }
+
+    /// Every name for a hash joined with ` | `, as xxhash32 collisions are common.
+    fn name(&mut self, hash: u32) -> Option<String> {
+        let entries = self.dictionary.get(hash);
+
+        if entries.is_empty() {
+            return None;
+        }
+
+        let mut names: Vec<&str> = Vec::new();
+        for entry in entries.iter() {
+            if !names.contains(&entry.value.as_str()) {
+                names.push(&entry.value);
+            }
+        }
+
+        self.resolved += 1;
+        Some(names.join(" | "))
+    }
+
+    /// Resolves a whole token, either 8 hex digits with an optional `0x` or a decimal u32.
+    fn resolve_token(&mut self, token: &str) -> Option<String> {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+
+        if digits.len() == 8 {
+            if let Some(name) = parse_hash(digits).and_then(|hash| self.name(hash)) {
+                return Some(name);
+            }
+        }
+
+        if self.numbers && digits.len() == token.len() {
+            return token.parse::<u32>().ok().and_then(|hash| self.name(hash));
+        }
+
+        None
+    }
+
+    fn resolve_value(&mut self, value: &Value) -> Option<String> {
+        match value {
+            Value::String(token) => self.resolve_token(token),
+            Value::Number(number) if self.numbers => number
+                .as_u64()
+                .and_then(|number| u32::try_from(number).ok())
+                .and_then(|hash| self.name(hash)),
+            _ => None,
+        }
+    }
+
+    /// Annotates every string and number in a JSON document.
+    ///
+    /// Object fields get a `{field}_name` sibling right after them, holding an array of names (or
+    /// nulls) when the field is an array. Bare array elements can only be annotated with `replace`.
+    pub fn json(&mut self, value: &mut Json) {
+        match value {
+            Json::Object(fields) => self.json_object(fields),
+            Json::Array(values) => {
+                for value in values {
+                    self.json_element(value);
+                }
+            }
+            Json::Scalar(scalar) if self.replace => {
+                if let Some(name) = self.resolve_value(scalar) {
+                    *scalar = Value::String(name);
+                }
+            }
+            Json::Scalar(_) => {}
+        }
+    }
+
+    fn json_element(&mut self, value: &mut Json) {
+        if !matches!(value, Json::Scalar(_)) || self.replace {
+            self.json(value);
+        }
+    }
+
+    fn json_object(&mut self, fields: &mut Vec<(String, Json)>) {
+        let keys: HashSet<String> = fields.iter().map(|(key, _)| key.clone()).collect();
+        let original = std::mem::take(fields);
+
+        for (key, mut value) in original {
+            let sibling = match &mut value {
+                Json::Object(_) => {
+                    self.json(&mut value);
+                    None
+                }
+                Json::Array(values) if !self.replace => {
+                    let names: Vec<Option<String>> = values
+                        .iter_mut()
+                        .map(|value| match value {
+                            Json::Scalar(scalar) => self.resolve_value(scalar),
+                            _ => {
+                                self.json(value);
+                                None
+                            }
+                        })
+                        .collect();
+
+                    names.iter().any(Option::is_some).then(|| {
+                        Json::Array(
+                            names
+                                .into_iter()
+                                .map(|name| Json::Scalar(name.map_or(Value::Null, Value::String)))
+                                .collect(),
+                        )
+                    })
+                }
+                Json::Array(_) => {
+                    self.json(&mut value);
+                    None
+                }
+                Json::Scalar(scalar) => match self.resolve_value(scalar) {
+                    Some(name) if self.replace => {
+                        *scalar = Value::String(name);
+                        None
+                    }
+                    name => name.map(|name| Json::Scalar(Value::String(name))),
+                },
+            };
+
+            let sibling_key = format!("{}_name", key);
+            fields.push((key, value));
+
+            // Never overwrite a field the document already has.
+            if let Some(sibling) = sibling.filter(|_| !keys.contains(&sibling_key)) {
+                fields.push((sibling_key, sibling));
+            }
+        }
+    }
+
+    /// Annotates every cell of a CSV. Columns with any match get a `{column}_name` column
+    /// inserted after them, unless replacing.
+    pub fn delimited(
+        &mut self,
+        input: &[u8],
+        delimiter: u8,
+        headers: bool,
+        output: impl Write,
+    ) -> anyhow::Result<()> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(headers)
+            .flexible(true)
+            .from_reader(input);
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_writer(output);
+
+        let header = if headers {
+            Some(reader.headers()?.clone())
+        } else {
+            None
+        };
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let names: Vec<Option<String>> =
+                record.iter().map(|cell| self.resolve_token(cell)).collect();
+            rows.push((record, names));
+        }
+
+        let columns = rows.iter().map(|(record, _)| record.len()).max();
+        let annotated: Vec<bool> = (0..columns.unwrap_or_default())
+            .map(|column| {
+                !self.replace
+                    && rows
+                        .iter()
+                        .any(|(_, names)| names.get(column).is_some_and(Option::is_some))
+            })
+            .collect();
+
+        if let Some(header) = header {
+            let mut fields = Vec::new();
+
+            for (column, field) in header.iter().enumerate() {
+                fields.push(field.to_string());
+
+                if annotated.get(column) == Some(&true) {
+                    fields.push(format!("{}_name", field));
+                }
+            }
+
+            writer.write_record(&fields)?;
+        }
+
+        for (record, names) in rows {
+            let mut fields = Vec::new();
+
+            for (column, (cell, name)) in record.iter().zip(names).enumerate() {
+                match name {
+                    Some(name) if self.replace => fields.push(name),
+                    name => {
+                        fields.push(cell.to_string());
+
+                        if annotated[column] {
+                            fields.push(name.unwrap_or_default());
+                        }
+                    }
+                }
+            }
+
+            writer.write_record(&fields)?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Annotates free text, appending ` (name)` after each resolved token or replacing it.
+    pub fn text(&mut self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let token = &rest[..end];
+
+            match self.resolve_token(token) {
+                Some(name) if self.replace => output.push_str(&name),
+                Some(name) => {
+                    output.push_str(token);
+                    output.push_str(" (");
+                    output.push_str(&name);
+                    output.push(')');
+                }
+                None => output.push_str(token),
+            }
+
+            rest = &rest[end..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::Entry;
+
+    fn dictionary() -> Dictionary {
+        let mut dictionary = Dictionary::default();
+        dictionary.insert(
+            0x48110BA3,
+            Entry {
+                value: "WEP_PL1500_04_03".to_string(),
+                source: "weapons.csv".to_string(),
+                text: None,
+            },
+        );
+        dictionary
+    }
+
+    #[test]
+    fn annotates_json_with_siblings() {
+        let dictionary = dictionary();
+        let input = r#"{
+            "weapon": "48110BA3",
+            "id": 1209076643,
+            "ids": ["0x48110BA3", "ffffffff"],
+            "other": "12345678",
+            "count": 3
+        }"#;
+
+        let mut value: Json = serde_json::from_str(input).unwrap();
+        Annotator::new(&dictionary).json(&mut value);
+
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            concat!(
+                r#"{"weapon":"48110BA3","weapon_name":"WEP_PL1500_04_03","id":1209076643,"#,
+                r#""ids":["0x48110BA3","ffffffff"],"ids_name":["WEP_PL1500_04_03",null],"#,
+                r#""other":"12345678","count":3}"#
+            )
+        );
+
+        let mut value: Json = serde_json::from_str(input).unwrap();
+        let mut annotator = Annotator::new(&dictionary);
+        annotator.numbers = true;
+        annotator.replace = true;
+        annotator.json(&mut value);
+
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            concat!(
+                r#"{"weapon":"WEP_PL1500_04_03","id":"WEP_PL1500_04_03","#,
+                r#""ids":["WEP_PL1500_04_03","ffffffff"],"other":"12345678","count":3}"#
+            )
+        );
+    }
+
+    #[test]
+    fn annotates_csv_and_text() {
+        let dictionary = dictionary();
+        let mut annotator = Annotator::new(&dictionary);
+
+        let mut output = Vec::new();
+        annotator
+            .delimited(b"id,count\n48110BA3,1\nabc,2\n", b',', true, &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,id_name,count\n48110BA3,WEP_PL1500_04_03,1\nabc,,2\n"
+        );
+
+        assert_eq!(
+            annotator.text("got 0x48110BA3, not 148110BA3"),
+            "got 0x48110BA3 (WEP_PL1500_04_03), not 148110BA3"
+        );
+
+        annotator.replace = true;
+        assert_eq!(annotator.text("[48110ba3]"), "[WEP_PL1500_04_03]");
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::dictionary::Dictionary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    Rust,
+    C,
+    Csharp,
+    Imhex,
+}
+
+impl Language {
+    /// Reserved words that variant names are escaped from. Matched ignoring case, so a name
+    /// like `Type` doesn't become a keyword once re-cased by whatever consumes the code.
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+                "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for",
+                "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut",
+                "override", "priv", "pub", "ref", "return", "self", "static", "struct", "super",
+                "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+                "where", "while", "yield",
+            ],
+            Language::C => &[
+                "auto", "bool", "break", "case", "char", "const", "continue", "default", "do",
+                "double", "else", "enum", "extern", "false", "float", "for", "goto", "if",
+                "inline", "int", "long", "nullptr", "register", "restrict", "return", "short",
+                "signed", "sizeof", "static", "struct", "switch", "true", "typedef", "typeof",
+                "union", "unsigned", "void", "volatile", "while",
+            ],
+            Language::Csharp => &[
+                "abstract",
+                "as",
+                "base",
+                "bool",
+                "break",
+                "byte",
+                "case",
+                "catch",
+                "char",
+                "checked",
+                "class",
+                "const",
+                "continue",
+                "decimal",
+                "default",
+                "delegate",
+                "do",
+                "double",
+                "else",
+                "enum",
+                "event",
+                "explicit",
+                "extern",
+                "false",
+                "finally",
+                "fixed",
+                "float",
+                "for",
+                "foreach",
+                "goto",
+                "if",
+                "implicit",
+                "in",
+                "int",
+                "interface",
+                "internal",
+                "is",
+                "lock",
+                "long",
+                "namespace",
+                "new",
+                "null",
+                "object",
+                "operator",
+                "out",
+                "override",
+                "params",
+                "private",
+                "protected",
+                "public",
+                "readonly",
+                "ref",
+                "return",
+                "sbyte",
+                "sealed",
+                "short",
+                "sizeof",
+                "stackalloc",
+                "static",
+                "string",
+                "struct",
+                "switch",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "typeof",
+                "uint",
+                "ulong",
+                "unchecked",
+                "unsafe",
+                "ushort",
+                "using",
+                "virtual",
+                "void",
+                "volatile",
+                "while",
+            ],
+            Language::Imhex => &[
+                "auto",
+                "be",
+                "bitfield",
+                "bool",
+                "break",
+                "catch",
+                "char",
+                "char16",
+                "continue",
+                "double",
+                "else",
+                "enum",
+                "false",
+                "float",
+                "fn",
+                "for",
+                "if",
+                "import",
+                "in",
+                "le",
+                "match",
+                "namespace",
+                "null",
+                "out",
+                "padding",
+                "parent",
+                "ref",
+                "return",
+                "s128",
+                "s16",
+                "s24",
+                "s32",
+                "s48",
+                "s64",
+                "s8",
+                "str",
+                "struct",
+                "this",
+                "true",
+                "try",
+                "u128",
+                "u16",
+                "u24",
+                "u32",
+                "u48",
+                "u64",
+                "u8",
+                "union",
+                "using",
+                "while",
+            ],
+        }
+    }
+
+    fn is_keyword(&self, identifier: &str) -> bool {
+        self.keywords()
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(identifier))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub hash: u32,
+    /// The original text the name was derived from.
+    pub label: String,
+}
+
+/// An enum of hashes with unique, sanitised variant names.
+#[derive(Debug)]
+pub struct HashEnum {
+    pub name: String,
+    pub variants: Vec<Variant>,
+}
+
+impl HashEnum {
+    /// Builds an enum from a dictionary, using the first candidate name of every hash.
+    ///
+    /// Entries from extracted `data/{lang}/*.json` files are named after their text and everything
+    /// else after its value. Variants are sorted by their key, then hash, and duplicate names get
+    /// a numeric suffix in that order so the output only changes when the data does.
+    pub fn from_dictionary(name: &str, dictionary: &Dictionary, language: Language) -> Self {
+        let mut entries: Vec<(u32, String, String)> = dictionary
+            .iter()
+            .filter_map(|(hash, entries)| {
+                let entry = entries.first()?;
+                let label = entry.text.as_ref().unwrap_or(&entry.value);
+                Some((hash, entry.value.clone(), label.clone()))
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.1, a.0).cmp(&(&b.1, b.0)));
+
+        Self::new(
+            name,
+            entries.into_iter().map(|(hash, _, label)| (hash, label)),
+            language,
+        )
+    }
+
+    /// Names variants after their labels, escaping `language`'s keywords with a trailing `_`.
+    pub fn new(
+        name: &str,
+        entries: impl IntoIterator<Item = (u32, String)>,
+        language: Language,
+    ) -> Self {
+        let mut used = HashSet::new();
+        let mut variants = Vec::new();
+
+        for (hash, label) in entries {
+            let mut base =
+                sanitize_identifier(&label).unwrap_or_else(|| format!("Unknown_{:08X}", hash));
+
+            if language.is_keyword(&base) {
+                base.push('_');
+            }
+
+            let mut name = base.clone();
+            let mut suffix = 1;
+
+            while !used.insert(name.clone()) {
+                suffix += 1;
+                name = format!("{}_{}", base, suffix);
+            }
+
+            variants.push(Variant { name, hash, label });
+        }
+
+        Self {
+            name: name.to_string(),
+            variants,
+        }
+    }
+
+    pub fn generate(&self, language: Language, namespace: Option<&str>) -> String {
+        match language {
+            Language::Rust => self.to_rust(),
+            Language::C => self.to_c(),
+            Language::Csharp => self.to_csharp(namespace),
+            Language::Imhex => imhex_pattern(std::slice::from_ref(self), namespace),
+        }
+    }
+
+    fn to_rust(&self) -> String {
+        let mut output = String::new();
+        let name = &self.name;
+
+        writeln!(output, "// Generated by xxhash32 codegen, do not edit.").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "#[allow(non_camel_case_types)]").unwrap();
+        writeln!(output, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]").unwrap();
+        writeln!(output, "#[repr(u32)]").unwrap();
+        writeln!(output, "pub enum {} {{", name).unwrap();
+        for variant in &self.variants {
+            writeln!(output, "    {} = {:#010X},", variant.name, variant.hash).unwrap();
+        }
+        writeln!(output, "}}").unwrap();
+        writeln!(output).unwrap();
+
+        writeln!(output, "impl TryFrom<u32> for {} {{", name).unwrap();
+        writeln!(output, "    type Error = u32;").unwrap();
+        writeln!(output).unwrap();
+        writeln!(
+            output,
+            "    fn try_from(value: u32) -> Result<Self, Self::Error> {{"
+        )
+        .unwrap();
+        writeln!(output, "        match value {{").unwrap();
+        for variant in &self.variants {
+            writeln!(
+                output,
+                "            {:#010X} => Ok(Self::{}),",
+                variant.hash, variant.name
+            )
+            .unwrap();
+        }
+        writeln!(output, "            _ => Err(value),").unwrap();
+        writeln!(output, "        }}").unwrap();
+        writeln!(output, "    }}").unwrap();
+        writeln!(output, "}}").unwrap();
+        writeln!(output).unwrap();
+
+        writeln!(output, "impl std::fmt::Display for {} {{", name).unwrap();
+        writeln!(
+            output,
+            "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{"
+        )
+        .unwrap();
+        writeln!(output, "        f.write_str(match self {{").unwrap();
+        for variant in &self.variants {
+            writeln!(
+                output,
+                "            Self::{} => {:?},",
+                variant.name, variant.label
+            )
+            .unwrap();
+        }
+        writeln!(output, "        }})").unwrap();
+        writeln!(output, "    }}").unwrap();
+        writeln!(output, "}}").unwrap();
+
+        output
+    }
+
+    fn to_c(&self) -> String {
+        let mut output = String::new();
+        let name = &self.name;
+
+        writeln!(output, "// Generated by xxhash32 codegen, do not edit.").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "#pragma once").unwrap();
+        writeln!(output).unwrap();
+        writeln!(output, "#include <stdint.h>").unwrap();
+        writeln!(output).unwrap();
+        // Enumerators are limited to int before C23, which most hashes don't fit in.
+        writeln!(output, "typedef uint32_t {};", name).unwrap();
+        writeln!(output).unwrap();
+        for variant in &self.variants {
+            writeln!(
+                output,
+                "#define {}_{} (({}){:#010X}u)",
+                name, variant.name, name, variant.hash
+            )
+            .unwrap();
+        }
+        writeln!(output).unwrap();
+
+        writeln!(
+            output,
+            "static inline const char *{}_to_string({} value) {{",
+            name, name
+        )
+        .unwrap();
+        writeln!(output, "    switch (value) {{").unwrap();
+        for variant in &self.variants {
+            writeln!(
+                output,
+                "    case {:#010X}u: return {};",
+                variant.hash,
+                c_string_literal(&variant.label)
+            )
+            .unwrap();
+        }
+        writeln!(output, "    default: return 0;").unwrap();
+        writeln!(output, "    }}").unwrap();
+        writeln!(output, "}}").unwrap();
+
+        output
+    }
+
+    fn to_csharp(&self, namespace: Option<&str>) -> String {
+        let mut output = String::new();
+        let indent = if namespace.is_some() { "    " } else { "" };
+
+        writeln!(output, "// Generated by xxhash32 codegen, do not edit.").unwrap();
+        writeln!(output).unwrap();
+
+        if let Some(namespace) = namespace {
+            writeln!(output, "namespace {}", namespace).unwrap();
+            writeln!(output, "{{").unwrap();
+        }
+
+        writeln!(output, "{}public enum {} : uint", indent, self.name).unwrap();
+        writeln!(output, "{}{{", indent).unwrap();
+        for variant in &self.variants {
+            writeln!(
+                output,
+                "{}    {} = {:#010X},",
+                indent, variant.name, variant.hash
+            )
+            .unwrap();
+        }
+        writeln!(output, "{}}}", indent).unwrap();
+
+        if namespace.is_some() {
+            writeln!(output, "}}").unwrap();
+        }
+
+        output
+    }
+}
+
+/// Writes enums as an ImHex pattern include, e.g. `imhex/includes/gbfr/enum.pat`.
+pub fn imhex_pattern(enums: &[HashEnum], namespace: Option<&str>) -> String {
+    let mut output = String::new();
+    let indent = if namespace.is_some() { "\t" } else { "" };
+
+    writeln!(output, "#pragma once").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "// Generated by xxhash32 codegen, do not edit.").unwrap();
+    writeln!(output).unwrap();
+
+    if let Some(namespace) = namespace {
+        writeln!(output, "namespace {} {{", namespace).unwrap();
+    }
+
+    for (i, hash_enum) in enums.iter().enumerate() {
+        if i > 0 {
+            writeln!(output).unwrap();
+        }
+
+        writeln!(output, "{}enum {}: u32 {{", indent, hash_enum.name).unwrap();
+        for variant in &hash_enum.variants {
+            writeln!(
+                output,
+                "{}\t{} = {:#010X},",
+                indent, variant.name, variant.hash
+            )
+            .unwrap();
+        }
+        writeln!(output, "{}}};", indent).unwrap();
+    }
+
+    if namespace.is_some() {
+        writeln!(output, "}}").unwrap();
+    }
+
+    output
+}
+
+/// Turns display text into a PascalCase identifier, e.g. `High-grade Scrap` into `HighgradeScrap`.
+///
+/// Words are split on whitespace, everything else that isn't an ASCII letter, digit or underscore
+/// is dropped. Returns `None` if nothing usable is left, including only underscores, which isn't
+/// an identifier in Rust.
+pub fn sanitize_identifier(text: &str) -> Option<String> {
+    let mut identifier = String::new();
+
+    for word in text.split_whitespace() {
+        let mut chars = word
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_');
+
+        if let Some(first) = chars.next() {
+            identifier.push(first.to_ascii_uppercase());
+            identifier.extend(chars);
+        }
+    }
+
+    if identifier.chars().all(|c| c == '_') {
+        return None;
+    }
+
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+
+    Some(identifier)
+}
+
+fn c_string_literal(text: &str) -> String {
+    let mut literal = String::from("\"");
+
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_ascii() && !c.is_ascii_control() => literal.push(c),
+            // Escape everything else as UTF-8 bytes so the header stays ASCII.
+            c => {
+                let mut buffer = [0; 4];
+                for byte in c.encode_utf8(&mut buffer).bytes() {
+                    write!(literal, "\\x{:02X}\"\"", byte).unwrap();
+                }
+            }
+        }
+    }
+
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_identifiers() {
+        assert_eq!(
+            sanitize_identifier("High-grade Scrap").unwrap(),
+            "HighgradeScrap"
+        );
+        assert_eq!(sanitize_identifier("Disast Ore").unwrap(), "DisastOre");
+        assert_eq!(
+            sanitize_identifier("Siero's Ticket").unwrap(),
+            "SierosTicket"
+        );
+        assert_eq!(
+            sanitize_identifier("WEP_PL1500_04_03").unwrap(),
+            "WEP_PL1500_04_03"
+        );
+        assert_eq!(sanitize_identifier("1st Place").unwrap(), "_1stPlace");
+        assert_eq!(sanitize_identifier("???"), None);
+        assert_eq!(sanitize_identifier("_"), None);
+        assert_eq!(sanitize_identifier("- __ -"), None);
+    }
+
+    #[test]
+    fn escapes_keywords() {
+        let names = |language: Language| -> Vec<String> {
+            let entries = ["self", "Type", "Struct", "u8", "Item"]
+                .into_iter()
+                .enumerate()
+                .map(|(hash, label)| (hash as u32, label.to_string()));
+
+            HashEnum::new("ItemID", entries, language)
+                .variants
+                .into_iter()
+                .map(|variant| variant.name)
+                .collect()
+        };
+
+        assert_eq!(
+            names(Language::Rust),
+            ["Self_", "Type_", "Struct_", "U8", "Item"]
+        );
+        assert_eq!(
+            names(Language::Csharp),
+            ["Self", "Type", "Struct_", "U8", "Item"]
+        );
+        assert_eq!(
+            names(Language::Imhex),
+            ["Self", "Type", "Struct_", "U8_", "Item"]
+        );
+    }
+
+    #[test]
+    fn suffixes_duplicate_names() {
+        let hash_enum = HashEnum::new(
+            "ItemID",
+            [
+                (0x463ABC2D, "Sequestration Wrightstone".to_string()),
+                (0x19B187D7, "Sequestration Wrightstone".to_string()),
+                (0x12345678, "???".to_string()),
+            ],
+            Language::Rust,
+        );
+
+        let names: Vec<&str> = hash_enum.variants.iter().map(|v| v.name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "SequestrationWrightstone",
+                "SequestrationWrightstone_2",
+                "Unknown_12345678"
+            ]
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rmpv::Value;
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use xxhash32_lib::xxhash32_custom;
+
+use crate::dictionary::{extension, parse_hash, Dictionary};
+
+/// Hashes seen in game data, along with every source they were seen in.
+#[derive(Debug, Default)]
+pub struct Observed {
+    hashes: BTreeMap<u32, BTreeSet<String>>,
+    /// Names seen alongside their hashes, such as unhashed keys in a .msg file.
+    names: BTreeMap<u32, String>,
+}
+
+/// Parses an 8-digit hex hash, as stored by the game for keys it only has the hash of.
+fn parse_stored_hash(value: &str) -> Option<u32> {
+    if value.len() == 8 {
+        parse_hash(value)
+    } else {
+        None
+    }
+}
+
+/// Quotes a sqlite table or column name, doubling any `"` inside it.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl Observed {
+    pub fn insert(&mut self, hash: u32, source: &str) {
+        self.hashes
+            .entry(hash)
+            .or_default()
+            .insert(source.to_string());
+    }
+
+    /// Adds a key that's either an 8-digit hex hash or a name, which is hashed and counts as
+    /// resolved.
+    pub fn insert_key(&mut self, key: &str, source: &str) {
+        match parse_stored_hash(key) {
+            Some(hash) => self.insert(hash, source),
+            None => {
+                let hash = xxhash32_custom(key.as_bytes());
+                self.insert(hash, source);
+                self.names.entry(hash).or_insert_with(|| key.to_string());
+            }
+        }
+    }
+
+    /// Loads a file with one hash per line, such as `coverage --unresolved-output` or the first
+    /// column of `hash-file` and `lookup` output.
+    pub fn load_hash_list(&mut self, path: &Path) -> anyhow::Result<()> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open hash list at path: {}", path.display()))?;
+        let source = path.display().to_string();
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let first_column = line
+                .split(|c: char| c == ',' || c == '\t' || c.is_whitespace())
+                .next()
+                .unwrap_or_default();
+
+            if let Some(hash) = parse_hash(first_column) {
+                self.insert(hash, &source);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads `scan` output, where each line is `offset hash name`.
+    pub fn load_scan_results(&mut self, path: &Path) -> anyhow::Result<()> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open scan results at path: {}", path.display()))?;
+        let source = path.display().to_string();
+
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            let mut columns = line.split_whitespace();
+
+            let (Some(offset), Some(hash)) = (columns.next(), columns.next()) else {
+                continue;
+            };
+
+            let hash = parse_hash(offset).and(parse_hash(hash)).with_context(|| {
+                format!(
+                    "Expected `offset hash name` on line {} of scan results: {}",
+                    number + 1,
+                    path.display()
+                )
+            })?;
+
+            self.insert(hash, &source);
+        }
+
+        Ok(())
+    }
+
+    /// Loads every value of `table.column` from a sqlite database such as `system_table.sqlite`.
+    ///
+    /// Integer values are used as-is, skipping any outside the u32 range. Text values are 8-digit
+    /// hex hashes or names, which are hashed.
+    pub fn load_sqlite(&mut self, path: &Path, table_column: &str) -> anyhow::Result<()> {
+        let (table, column) = table_column
+            .split_once('.')
+            .with_context(|| format!("Expected table.column, got: {}", table_column))?;
+
+        let db = Connection::open(path)
+            .with_context(|| format!("Could not open sqlite db at path: {}", path.display()))?;
+        let column = quote_identifier(column);
+        let mut statement = db.prepare(&format!(
+            "SELECT {} FROM {} WHERE {} IS NOT NULL",
+            column,
+            quote_identifier(table),
+            column
+        ))?;
+        let mut rows = statement.query([])?;
+        let mut out_of_range = 0;
+
+        while let Some(row) = rows.next()? {
+            match row.get_ref(0)? {
+                ValueRef::Integer(value) => match u32::try_from(value) {
+                    Ok(hash) => self.insert(hash, table_column),
+                    Err(_) => out_of_range += 1,
+                },
+                ValueRef::Text(value) => {
+                    if let Ok(value) = std::str::from_utf8(value) {
+                        self.insert_key(value, table_column);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if out_of_range > 0 {
+            eprintln!(
+                "Skipped {} values of {} that don't fit in a u32",
+                out_of_range, table_column
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Loads the `id_hash_` of every row of a .msg file, or of every .msg file in a directory.
+    pub fn load_msg(&mut self, path: &Path) -> anyhow::Result<()> {
+        if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .with_context(|| format!("Could not read directory: {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir() || extension(path).as_deref() == Some("msg"))
+                .collect();
+            files.sort();
+
+            for file in files {
+                self.load_msg(&file)?;
+            }
+
+            return Ok(());
+        }
+
+        let file = File::open(path)
+            .with_context(|| format!("Could not open language file at path: {}", path.display()))?;
+        let value = rmpv::decode::read_value(&mut BufReader::new(file))
+            .with_context(|| format!("Could not read language file: {}", path.display()))?;
+        let source = path.display().to_string();
+
+        fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+            value
+                .as_map()?
+                .iter()
+                .find_map(|(key, value)| (key.as_str() == Some(name)).then_some(value))
+        }
+
+        let rows = field(&value, "rows_")
+            .and_then(Value::as_array)
+            .with_context(|| format!("Expected a rows_ array in: {}", path.display()))?;
+
+        for row in rows {
+            let id_hash = field(row, "column_")
+                .and_then(|column| field(column, "id_hash_"))
+                .and_then(Value::as_str);
+
+            if let Some(id_hash) = id_hash {
+                self.insert_key(id_hash, &source);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Counts {
+    pub resolved: usize,
+    pub unresolved: usize,
+}
+
+impl Counts {
+    fn add(&mut self, resolved: bool) {
+        if resolved {
+            self.resolved += 1;
+        } else {
+            self.unresolved += 1;
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.resolved + self.unresolved
+    }
+
+    pub fn percentage(&self) -> f64 {
+        if self.total() == 0 {
+            return 0.0;
+        }
+
+        self.resolved as f64 * 100.0 / self.total() as f64
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    pub total: Counts,
+    pub by_source: BTreeMap<String, Counts>,
+    /// Resolved hashes counted by the prefix of their name, e.g. `WEP` for `WEP_PL1500_04_03`.
+    pub by_prefix: BTreeMap<String, usize>,
+    pub unresolved: BTreeMap<u32, BTreeSet<String>>,
+}
+
+impl CoverageReport {
+    pub fn new(observed: &Observed, dictionary: &Dictionary) -> Self {
+        let mut report = CoverageReport::default();
+
+        for (hash, sources) in &observed.hashes {
+            let entries = dictionary.get(*hash);
+            let name = entries
+                .first()
+                .map(|entry| entry.value.as_str())
+                .or_else(|| observed.names.get(hash).map(String::as_str));
+            let resolved = name.is_some();
+
+            report.total.add(resolved);
+
+            for source in sources {
+                report
+                    .by_source
+                    .entry(source.clone())
+                    .or_default()
+                    .add(resolved);
+            }
+
+            match name {
+                Some(name) => *report.by_prefix.entry(key_prefix(name)).or_default() += 1,
+                None => {
+                    report.unresolved.insert(*hash, sources.clone());
+                }
+            }
+        }
+
+        report
+    }
+
+    pub fn print(&self, list_unresolved: bool) {
+        let width = self
+            .by_source
+            .keys()
+            .map(String::len)
+            .chain(["Source".len(), "Total".len()])
+            .max()
+            .unwrap_or_default();
+
+        println!(
+            "{:<width$}  {:>8}  {:>10}  {:>8}  {:>8}",
+            "Source", "Resolved", "Unresolved", "Total", "Coverage"
+        );
+
+        for (source, counts) in self
+            .by_source
+            .iter()
+            .chain(std::iter::once((&"Total".to_string(), &self.total)))
+        {
+            println!(
+                "{:<width$}  {:>8}  {:>10}  {:>8}  {:>7.1}%",
+                source,
+                counts.resolved,
+                counts.unresolved,
+                counts.total(),
+                counts.percentage()
+            );
+        }
+
+        println!();
+        println!("{:<16}  {:>8}", "Prefix", "Resolved");
+
+        for (prefix, count) in &self.by_prefix {
+            println!("{:<16}  {:>8}", prefix, count);
+        }
+
+        if list_unresolved && !self.unresolved.is_empty() {
+            println!();
+            println!("Unresolved:");
+
+            for (hash, sources) in &self.unresolved {
+                let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+                println!("{:#010X} [{}]", hash, sources.join(", "));
+            }
+        }
+    }
+
+    /// Writes unresolved hashes one per line, as read by `repl --target`.
+    pub fn write_targets(&self, output: &mut impl Write) -> anyhow::Result<()> {
+        for hash in self.unresolved.keys() {
+            writeln!(output, "{:#010X}", hash)?;
+        }
+
+        output.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Infers the category of a name from its leading letters, e.g. `WEP_PL1500_04_03` is `WEP`,
+/// `Pl0000` is `Pl` and `ITEM_01_0001` is `ITEM`.
+pub fn key_prefix(name: &str) -> String {
+    let prefix: String = name.chars().take_while(|c| c.is_alphabetic()).collect();
+
+    if prefix.is_empty() {
+        "(none)".to_string()
+    } else {
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::Entry;
+
+    #[test]
+    fn counts_resolved_hashes_by_source_and_prefix() {
+        let mut dictionary = Dictionary::default();
+        dictionary.insert(
+            0x48110BA3,
+            Entry {
+                value: "WEP_PL1500_04_03".to_string(),
+                source: "weapons.csv".to_string(),
+                text: None,
+            },
+        );
+
+        let mut observed = Observed::default();
+        observed.insert(0x48110BA3, "weapon.Key");
+        observed.insert(0x12345678, "weapon.Key");
+        observed.insert(0x12345678, "scan.txt");
+
+        let report = CoverageReport::new(&observed, &dictionary);
+
+        assert_eq!(report.total.resolved, 1);
+        assert_eq!(report.total.unresolved, 1);
+        assert_eq!(report.by_source["weapon.Key"].total(), 2);
+        assert_eq!(report.by_source["scan.txt"].unresolved, 1);
+        assert_eq!(report.by_prefix["WEP"], 1);
+        assert!(report.unresolved.contains_key(&0x12345678));
+    }
+
+    fn hashes(observed: &Observed) -> Vec<u32> {
+        observed.hashes.keys().copied().collect()
+    }
+
+    #[test]
+    fn loads_hash_lists_and_scan_results() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let hash_list = dir.path().join("unresolved.txt");
+        std::fs::write(&hash_list, "0x48110BA3\n12345678,name\n").unwrap();
+        let mut observed = Observed::default();
+        observed.load_hash_list(&hash_list).unwrap();
+        assert_eq!(hashes(&observed), [0x12345678, 0x48110BA3]);
+
+        let scan_results = dir.path().join("scan.txt");
+        std::fs::write(
+            &scan_results,
+            "0x00000010 0x48110BA3 WEP_PL1500_04_03\n0x00000020 0x0000FFFF\n",
+        )
+        .unwrap();
+        let mut observed = Observed::default();
+        observed.load_scan_results(&scan_results).unwrap();
+        assert_eq!(hashes(&observed), [0x0000FFFF, 0x48110BA3]);
+
+        std::fs::write(&scan_results, "0x00000010 WEP_PL1500_04_03\n").unwrap();
+        assert!(Observed::default()
+            .load_scan_results(&scan_results)
+            .is_err());
+    }
+
+    #[test]
+    fn hashes_names_in_sqlite_text_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("system_table.sqlite");
+        let db = Connection::open(&path).unwrap();
+        db.execute_batch(
+            r#"CREATE TABLE "weap""on" ("Ke""y");
+             INSERT INTO "weap""on" VALUES ('WEP_PL1500_04_03'), ('1a2b3c4d'), ('beef'), (65535),
+                 (NULL), (-1), (4294967296);"#,
+        )
+        .unwrap();
+        drop(db);
+
+        let mut observed = Observed::default();
+        observed.load_sqlite(&path, r#"weap"on.Ke"y"#).unwrap();
+
+        let report = CoverageReport::new(&observed, &Dictionary::default());
+        assert_eq!(report.total.resolved, 2);
+        assert_eq!(
+            report.unresolved.keys().copied().collect::<Vec<_>>(),
+            [0x0000FFFF, 0x1A2B3C4D]
+        );
+        assert_eq!(report.by_prefix["WEP"], 1);
+        assert_eq!(report.by_prefix["beef"], 1);
+    }
+
+    #[test]
+    fn loads_msg_id_hashes() {
+        let row = |id_hash: &str| {
+            Value::Map(vec![(
+                "column_".into(),
+                Value::Map(vec![
+                    ("id_hash_".into(), id_hash.into()),
+                    ("text_".into(), "text".into()),
+                ]),
+            )])
+        };
+        let msg = Value::Map(vec![(
+            "rows_".into(),
+            Value::Array(vec![row("TXT_PL1500"), row("48110ba3")]),
+        )]);
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("en")).unwrap();
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, &msg).unwrap();
+        std::fs::write(dir.path().join("en").join("text.msg"), bytes).unwrap();
+
+        let mut observed = Observed::default();
+        observed.load_msg(dir.path()).unwrap();
+
+        let report = CoverageReport::new(&observed, &Dictionary::default());
+        assert_eq!(report.total.resolved, 1);
+        assert_eq!(report.by_prefix["TXT"], 1);
+        assert!(report.unresolved.contains_key(&0x48110BA3));
+    }
+}
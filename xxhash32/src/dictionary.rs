@@ -0,0 +1,428 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde_json::Value;
+use xxhash32_lib::index::HashIndex;
+
+/// A single name known for a hash, along with where it came from.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub value: String,
+    pub source: String,
+    /// Display text, only set for entries loaded from extracted `data/{lang}/*.json` files.
+    pub text: Option<String>,
+}
+
+/// Hash to name mappings loaded from any number of dictionary files.
+///
+/// xxhash32 has a lot of collisions, so every hash can have several candidate names.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    entries: HashMap<u32, Vec<Entry>>,
+    /// Memory-mapped `build-index` files, looked up on demand instead of being loaded.
+    indexes: Vec<(String, HashIndex)>,
+}
+
+impl Dictionary {
+    /// Loads a dictionary file, or every dictionary file in a directory.
+    ///
+    /// `.json` files are read as `language-unpack` output or `hash-file --format json` output,
+    /// `.ndjson` and `.tsv` as the matching `hash-file` formats, `.idx` files are memory-mapped
+    /// as `build-index` output and anything else is read as a `hash,value` CSV.
+    pub fn load(&mut self, path: &Path) -> anyhow::Result<()> {
+        if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+                .with_context(|| format!("Could not read directory: {}", path.display()))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    matches!(
+                        extension(path).as_deref(),
+                        Some("csv" | "tsv" | "json" | "ndjson" | "idx")
+                    )
+                })
+                .collect();
+            files.sort();
+
+            for file in files {
+                self.load(&file)?;
+            }
+
+            return Ok(());
+        }
+
+        match extension(path).as_deref() {
+            Some("json") => self.load_json(path),
+            Some("ndjson") => self.load_ndjson(path),
+            Some("idx") => self.load_index(path),
+            Some("tsv") => self.load_delimited(path, b'\t'),
+            _ => self.load_delimited(path, b','),
+        }
+    }
+
+    /// Loads a `hash,value` CSV or TSV as written by `hash-file`.
+    pub fn load_delimited(&mut self, path: &Path, delimiter: u8) -> anyhow::Result<()> {
+        let source = source_name(path);
+
+        read_delimited(path, delimiter, |hash, value| {
+            self.insert(
+                hash,
+                Entry {
+                    value,
+                    source: source.clone(),
+                    text: None,
+                },
+            );
+        })
+    }
+
+    /// Loads an extracted `data/{lang}/*.json` file keyed by lowercase hex hashes, or an array
+    /// of `{ "hash", "value" }` objects.
+    ///
+    /// Keys that aren't hashes (such as quest IDs) are skipped.
+    pub fn load_json(&mut self, path: &Path) -> anyhow::Result<()> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open dictionary at path: {}", path.display()))?;
+        let json: Value = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Could not parse dictionary: {}", path.display()))?;
+        let source = source_name(path);
+
+        match json {
+            Value::Array(values) => {
+                for value in values {
+                    self.insert_hash_value_object(&value, &source);
+                }
+            }
+            Value::Object(object) => {
+                for (key, value) in object {
+                    if key.len() != 8 {
+                        continue;
+                    }
+
+                    let Some(hash) = parse_hash(&key) else {
+                        continue;
+                    };
+
+                    let (value, text) = match value {
+                        Value::Object(object) => (
+                            object
+                                .get("key")
+                                .and_then(Value::as_str)
+                                .map(str::to_string),
+                            // Merged `extract-all --layout merged` files have text per language.
+                            object
+                                .get("text")
+                                .and_then(|text| {
+                                    text.as_str()
+                                        .or_else(|| text.get("en").and_then(Value::as_str))
+                                })
+                                .map(str::to_string),
+                        ),
+                        Value::String(text) => (None, Some(text)),
+                        _ => (None, None),
+                    };
+
+                    self.insert(
+                        hash,
+                        Entry {
+                            value: value.unwrap_or_default(),
+                            source: source.clone(),
+                            text,
+                        },
+                    );
+                }
+            }
+            _ => anyhow::bail!("Unsupported dictionary format: {}", path.display()),
+        }
+
+        Ok(())
+    }
+
+    /// Loads one `{ "hash", "value" }` object per line, as written by `hash-file --format ndjson`.
+    pub fn load_ndjson(&mut self, path: &Path) -> anyhow::Result<()> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open dictionary at path: {}", path.display()))?;
+        let source = source_name(path);
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let value: Value = serde_json::from_str(&line)
+                .with_context(|| format!("Could not parse dictionary: {}", path.display()))?;
+            self.insert_hash_value_object(&value, &source);
+        }
+
+        Ok(())
+    }
+
+    pub fn load_index(&mut self, path: &Path) -> anyhow::Result<()> {
+        let index = HashIndex::open(path)
+            .with_context(|| format!("Could not open index at path: {}", path.display()))?;
+
+        self.indexes.push((source_name(path), index));
+
+        Ok(())
+    }
+
+    fn insert_hash_value_object(&mut self, object: &Value, source: &str) {
+        let hash = object.get("hash").and_then(|hash| match hash {
+            Value::String(hash) => parse_hash(hash),
+            Value::Number(hash) => hash.as_u64().and_then(|hash| u32::try_from(hash).ok()),
+            _ => None,
+        });
+        let value = object.get("value").and_then(Value::as_str);
+
+        if let (Some(hash), Some(value)) = (hash, value) {
+            self.insert(
+                hash,
+                Entry {
+                    value: value.to_string(),
+                    source: source.to_string(),
+                    text: None,
+                },
+            );
+        }
+    }
+
+    pub fn insert(&mut self, hash: u32, entry: Entry) {
+        self.entries.entry(hash).or_default().push(entry);
+    }
+
+    /// Every name for a hash. Only allocates when a memory-mapped index has names for it.
+    pub fn get(&self, hash: u32) -> Cow<'_, [Entry]> {
+        let mut entries = Cow::Borrowed(self.entries.get(&hash).map(Vec::as_slice).unwrap_or(&[]));
+
+        for (source, index) in &self.indexes {
+            let names = index.get(hash);
+
+            if !names.is_empty() {
+                entries.to_mut().extend(index_entries(source, names));
+            }
+        }
+
+        entries
+    }
+
+    /// Every hash with its names in ascending hash order, including those in memory-mapped
+    /// indexes, which are walked in order rather than looked up.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Cow<'_, [Entry]>)> + '_ {
+        let mut hashes: Vec<u32> = self.entries.keys().copied().collect();
+        hashes.sort_unstable();
+
+        let mut in_memory = hashes.into_iter().peekable();
+        let mut indexes: Vec<_> = self
+            .indexes
+            .iter()
+            .map(|(source, index)| (source, index.iter().peekable()))
+            .collect();
+
+        std::iter::from_fn(move || {
+            let hash = in_memory
+                .peek()
+                .copied()
+                .into_iter()
+                .chain(
+                    indexes
+                        .iter_mut()
+                        .filter_map(|(_, names)| names.peek().map(|(hash, _)| *hash)),
+                )
+                .min()?;
+
+            let mut entries = Cow::Borrowed(&[][..]);
+
+            if in_memory.next_if_eq(&hash).is_some() {
+                entries = Cow::Borrowed(self.entries[&hash].as_slice());
+            }
+
+            for (source, names) in &mut indexes {
+                if let Some((_, names)) = names.next_if(|(next, _)| *next == hash) {
+                    entries.to_mut().extend(index_entries(source, names));
+                }
+            }
+
+            Some((hash, entries))
+        })
+    }
+}
+
+fn index_entries<'a>(source: &'a str, names: Vec<&'a str>) -> impl Iterator<Item = Entry> + 'a {
+    names.into_iter().map(move |value| Entry {
+        value: value.to_string(),
+        source: source.to_string(),
+        text: None,
+    })
+}
+
+/// Streams `hash,value` pairs from a CSV or TSV without keeping them in memory.
+pub fn read_delimited(
+    path: &Path,
+    delimiter: u8,
+    callback: impl FnMut(u32, String),
+) -> anyhow::Result<()> {
+    let file = File::open(path)
+        .with_context(|| format!("Could not open dictionary at path: {}", path.display()))?;
+
+    read_delimited_from(BufReader::new(file), delimiter, callback)
+        .with_context(|| format!("Could not read dictionary: {}", path.display()))
+}
+
+/// Reads `hash,value` pairs, with or without a header row. Rows whose first column isn't a hash,
+/// such as a `hash,value` header, are skipped.
+///
+/// Older files don't quote their values, so extra columns are joined back together.
+pub fn read_delimited_from(
+    reader: impl Read,
+    delimiter: u8,
+    mut callback: impl FnMut(u32, String),
+) -> anyhow::Result<()> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+
+    for record in reader.records() {
+        let record = record?;
+
+        let Some(hash) = record.get(0).and_then(parse_hash) else {
+            continue;
+        };
+
+        let value = record
+            .iter()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .join(&(delimiter as char).to_string());
+
+        callback(hash, value);
+    }
+
+    Ok(())
+}
+
+pub fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+/// Names a source by its parent directory and file name, e.g. `en/weapons.json`.
+fn source_name(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+
+    match path.parent().and_then(Path::file_name) {
+        Some(parent) => format!("{}/{}", parent.to_string_lossy(), file_name),
+        None => file_name.to_string(),
+    }
+}
+
+/// Parses a hash written as hex, with or without a `0x` prefix.
+pub fn parse_hash(input: &str) -> Option<u32> {
+    let input = input.trim();
+    let digits = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+
+    if digits.is_empty() || digits.len() > 8 {
+        return None;
+    }
+
+    u32::from_str_radix(digits, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hashes() {
+        assert_eq!(parse_hash("0x48110BA3"), Some(0x48110BA3));
+        assert_eq!(parse_hash("48110ba3"), Some(0x48110BA3));
+        assert_eq!(parse_hash("0X0000ffff"), Some(0xFFFF));
+        assert_eq!(parse_hash("hash"), None);
+        assert_eq!(parse_hash("0x123456789"), None);
+    }
+
+    #[test]
+    fn iterates_entries_and_indexes_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.idx");
+        let mut builder = xxhash32_lib::index::HashIndexBuilder::new();
+        builder.insert(0x48110BA3, "WEP_PL1500_04_03");
+        builder.insert(0x9AD6310D, "hello");
+        builder.write(File::create(&path).unwrap()).unwrap();
+
+        let mut dictionary = Dictionary::default();
+        dictionary.load(&path).unwrap();
+        for (hash, value) in [(0xFFFFFFFF, "last"), (0x48110BA3, "collision")] {
+            dictionary.insert(
+                hash,
+                Entry {
+                    value: value.to_string(),
+                    source: "names.csv".to_string(),
+                    text: None,
+                },
+            );
+        }
+
+        let iterated: Vec<(u32, Vec<String>)> = dictionary
+            .iter()
+            .map(|(hash, entries)| {
+                (
+                    hash,
+                    entries.iter().map(|entry| entry.value.clone()).collect(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            iterated,
+            [
+                (
+                    0x48110BA3,
+                    vec!["collision".to_string(), "WEP_PL1500_04_03".to_string()]
+                ),
+                (0x9AD6310D, vec!["hello".to_string()]),
+                (0xFFFFFFFF, vec!["last".to_string()]),
+            ]
+        );
+        assert!(matches!(dictionary.get(0xFFFFFFFF), Cow::Borrowed(_)));
+        assert_eq!(dictionary.get(0x48110BA3).len(), 2);
+    }
+
+    #[test]
+    fn reads_csv_with_or_without_header() {
+        let read = |input: &str| {
+            let mut entries = Vec::new();
+            read_delimited_from(input.as_bytes(), b',', |hash, value| {
+                entries.push((hash, value))
+            })
+            .unwrap();
+            entries
+        };
+
+        let expected = vec![
+            (0x48110BA3, "WEP_PL1500_04_03".to_string()),
+            (0x0000FFFF, "a,b".to_string()),
+        ];
+
+        assert_eq!(
+            read("0x48110BA3,WEP_PL1500_04_03\n0x0000FFFF,a,b\n"),
+            expected
+        );
+        assert_eq!(
+            read("hash,value\n0x48110BA3,WEP_PL1500_04_03\n0x0000FFFF,\"a,b\"\n"),
+            expected
+        );
+    }
+}
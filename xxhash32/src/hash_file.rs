@@ -0,0 +1,352 @@
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::BufRead;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use xxhash32_lib::xxhash32_custom;
+
+use crate::output::DictionaryWriter;
+
+/// How many invalid UTF-8 lines are printed individually before only counting them.
+const MAX_REPORTED_INVALID_LINES: usize = 10;
+
+pub struct HashFileOptions {
+    pub threads: usize,
+    /// Number of lines handed to a worker at a time.
+    pub chunk_size: usize,
+    /// Write results in input order, otherwise chunks are written as soon as they're hashed.
+    pub ordered: bool,
+    /// Skip lines already written. Keeps a 64-bit hash of every unique line in memory, rather
+    /// than the line itself.
+    pub dedupe: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct HashFileStats {
+    pub lines: usize,
+    pub written: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+impl HashFileStats {
+    /// Prints a throughput summary to stderr.
+    pub fn report(&self) {
+        let seconds = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        let mebibytes = self.bytes as f64 / (1024.0 * 1024.0);
+
+        eprintln!(
+            "Hashed {} lines ({:.1} MiB) in {:.3}s, {:.0} lines/s, {:.1} MiB/s",
+            self.lines,
+            mebibytes,
+            seconds,
+            self.lines as f64 / seconds,
+            mebibytes / seconds
+        );
+        eprintln!(
+            "Wrote {} lines, skipped {} duplicates and {} invalid UTF-8 lines",
+            self.written, self.duplicates, self.invalid
+        );
+    }
+}
+
+struct Chunk {
+    index: usize,
+    /// Line number of the first line in the chunk, starting at 1.
+    first_line: usize,
+    lines: Vec<Vec<u8>>,
+}
+
+enum HashedLine {
+    Valid(u32, String),
+    Invalid(usize),
+}
+
+struct HashedChunk {
+    index: usize,
+    lines: Vec<HashedLine>,
+}
+
+/// Hashes every line of `input` across worker threads and writes the results to `writer`.
+///
+/// One thread reads chunks of lines, the workers hash them and the calling thread writes them
+/// out, reordering chunks when `ordered` is set. Lines that aren't valid UTF-8 are reported
+/// and skipped.
+pub fn hash_lines(
+    input: impl BufRead + Send,
+    writer: &mut DictionaryWriter,
+    options: &HashFileOptions,
+) -> anyhow::Result<HashFileStats> {
+    let start = Instant::now();
+    let threads = options.threads.max(1);
+    let chunk_size = options.chunk_size.max(1);
+
+    let (chunk_sender, chunk_receiver) = sync_channel::<Chunk>(threads * 2);
+    let (hashed_sender, hashed_receiver) = sync_channel::<HashedChunk>(threads * 2);
+    let chunk_receiver = Arc::new(Mutex::new(chunk_receiver));
+
+    std::thread::scope(|scope| {
+        let reader = scope.spawn(move || read_chunks(input, chunk_size, chunk_sender));
+
+        for _ in 0..threads {
+            let chunk_receiver = Arc::clone(&chunk_receiver);
+            let hashed_sender = hashed_sender.clone();
+
+            scope.spawn(move || loop {
+                let chunk = match chunk_receiver.lock().unwrap().recv() {
+                    Ok(chunk) => chunk,
+                    Err(_) => break,
+                };
+
+                if hashed_sender.send(hash_chunk(chunk)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        // Only the workers hold senders now, so the receiver closes once they're all done.
+        drop(hashed_sender);
+        // Likewise for the chunk receiver, so if writing fails and the workers stop, the reader's
+        // next send fails instead of blocking forever.
+        drop(chunk_receiver);
+
+        let mut stats = write_chunks(hashed_receiver, writer, options)?;
+
+        let (lines, bytes) = reader
+            .join()
+            .map_err(|_| anyhow::anyhow!("Reader thread panicked"))??;
+        stats.lines = lines;
+        stats.bytes = bytes;
+        stats.elapsed = start.elapsed();
+
+        Ok(stats)
+    })
+}
+
+/// Reads `input` into chunks of lines, returning the total number of lines and bytes read.
+fn read_chunks(
+    mut input: impl BufRead,
+    chunk_size: usize,
+    sender: std::sync::mpsc::SyncSender<Chunk>,
+) -> anyhow::Result<(usize, usize)> {
+    let mut lines = 0;
+    let mut bytes = 0;
+    let mut index = 0;
+
+    loop {
+        let mut chunk = Chunk {
+            index,
+            first_line: lines + 1,
+            lines: Vec::with_capacity(chunk_size),
+        };
+
+        while chunk.lines.len() < chunk_size {
+            let mut line = Vec::new();
+            let read = input.read_until(b'\n', &mut line)?;
+
+            if read == 0 {
+                break;
+            }
+
+            bytes += read;
+
+            // Matches BufRead::lines, which strips both \n and \r\n.
+            if line.last() == Some(&b'\n') {
+                line.pop();
+
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+            }
+
+            chunk.lines.push(line);
+        }
+
+        if chunk.lines.is_empty() {
+            break;
+        }
+
+        lines += chunk.lines.len();
+        index += 1;
+
+        if sender.send(chunk).is_err() {
+            break;
+        }
+    }
+
+    Ok((lines, bytes))
+}
+
+fn hash_chunk(chunk: Chunk) -> HashedChunk {
+    let lines = chunk
+        .lines
+        .into_iter()
+        .enumerate()
+        .map(|(offset, line)| {
+            let hash = xxhash32_custom(&line);
+
+            match String::from_utf8(line) {
+                Ok(line) => HashedLine::Valid(hash, line),
+                Err(_) => HashedLine::Invalid(chunk.first_line + offset),
+            }
+        })
+        .collect();
+
+    HashedChunk {
+        index: chunk.index,
+        lines,
+    }
+}
+
+/// A 64-bit hash for deduplication, where xxhash32's collisions would drop distinct lines.
+fn line_hash(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn write_chunks(
+    receiver: Receiver<HashedChunk>,
+    writer: &mut DictionaryWriter,
+    options: &HashFileOptions,
+) -> anyhow::Result<HashFileStats> {
+    let mut stats = HashFileStats::default();
+    let mut seen = HashSet::new();
+    let mut pending = BTreeMap::new();
+    let mut next_index = 0;
+
+    let mut write_chunk = |chunk: HashedChunk| -> anyhow::Result<()> {
+        for line in chunk.lines {
+            match line {
+                HashedLine::Valid(hash, value) => {
+                    if options.dedupe && !seen.insert(line_hash(&value)) {
+                        stats.duplicates += 1;
+                        continue;
+                    }
+
+                    writer.write(hash, &value)?;
+                    stats.written += 1;
+                }
+                HashedLine::Invalid(line_number) => {
+                    stats.invalid += 1;
+
+                    if stats.invalid <= MAX_REPORTED_INVALID_LINES {
+                        eprintln!("Skipping line {}: invalid UTF-8", line_number);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    };
+
+    for chunk in receiver {
+        if !options.ordered {
+            write_chunk(chunk)?;
+            continue;
+        }
+
+        pending.insert(chunk.index, chunk);
+
+        while let Some(chunk) = pending.remove(&next_index) {
+            write_chunk(chunk)?;
+            next_index += 1;
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{HashFormat, OutputFormat};
+
+    /// Fails every write once `remaining` bytes have been written.
+    struct FailingWriter {
+        remaining: usize,
+    }
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.remaining < buf.len() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::BrokenPipe,
+                    "closed",
+                ));
+            }
+
+            self.remaining -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn stops_when_writing_fails() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let input: Vec<u8> = (0..200_000)
+                .flat_map(|line| format!("line{}\n", line).into_bytes())
+                .collect();
+            let output = Box::new(FailingWriter { remaining: 50_000 });
+            let mut writer =
+                DictionaryWriter::new(output, OutputFormat::Csv, HashFormat::Upper).unwrap();
+
+            let options = HashFileOptions {
+                threads: 2,
+                chunk_size: 10,
+                ordered: true,
+                dedupe: false,
+            };
+
+            let _ = sender.send(hash_lines(input.as_slice(), &mut writer, &options).is_err());
+        });
+
+        let failed = receiver
+            .recv_timeout(Duration::from_secs(30))
+            .expect("hash_lines didn't return after the writer failed");
+        assert!(failed);
+    }
+
+    #[test]
+    fn hashes_lines_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hashes.csv");
+        let input: &[u8] = b"hello\r\nWEP_PL1500_04_03\n\xFF\nhello\nlast";
+        let mut writer =
+            DictionaryWriter::create(&path, OutputFormat::Csv, HashFormat::Upper).unwrap();
+
+        let options = HashFileOptions {
+            threads: 4,
+            chunk_size: 1,
+            ordered: true,
+            dedupe: true,
+        };
+
+        let stats = hash_lines(input, &mut writer, &options).unwrap();
+        writer.finish().unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(
+            written,
+            format!(
+                "hash,value\n0x9AD6310D,hello\n0x48110BA3,WEP_PL1500_04_03\n{:#010X},last\n",
+                xxhash32_custom(b"last")
+            )
+        );
+        assert_eq!(stats.lines, 5);
+        assert_eq!(stats.written, 3);
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(stats.invalid, 1);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use anyhow::Context;
+use serde_json::Value;
+use xxhash32_lib::xxhash32_custom;
+
+use crate::codegen::{HashEnum, Language};
+use crate::dictionary::parse_hash;
+
+/// How the keys of an extracted `data/{lang}/*.json` file map to the IDs stored in game data.
+#[derive(Clone, Copy)]
+enum KeyKind {
+    /// Keys are already lowercase hex hashes.
+    Hash,
+    /// Keys are the unhashed identifier, e.g. `Pl0000`.
+    HashOfKey,
+    /// Keys are a decimal number used as the ID itself, e.g. quest `101001` from `TXT_QR_101001`.
+    Number,
+}
+
+struct Category {
+    file_name: &'static str,
+    enum_name: &'static str,
+    keys: KeyKind,
+}
+
+/// Every category written by `language-unpack extract-all`, in the order they're generated.
+const CATEGORIES: [Category; 8] = [
+    Category {
+        file_name: "items.json",
+        enum_name: "ItemID",
+        keys: KeyKind::Hash,
+    },
+    Category {
+        file_name: "weapons.json",
+        enum_name: "WeaponID",
+        keys: KeyKind::Hash,
+    },
+    Category {
+        file_name: "sigils.json",
+        enum_name: "SigilID",
+        keys: KeyKind::Hash,
+    },
+    Category {
+        file_name: "traits.json",
+        enum_name: "TraitID",
+        keys: KeyKind::Hash,
+    },
+    Category {
+        file_name: "overmasteries.json",
+        enum_name: "OvermasteryID",
+        keys: KeyKind::Hash,
+    },
+    Category {
+        file_name: "enemies.json",
+        enum_name: "EnemyID",
+        keys: KeyKind::Hash,
+    },
+    Category {
+        file_name: "quests.json",
+        enum_name: "QuestID",
+        keys: KeyKind::Number,
+    },
+    Category {
+        file_name: "characters.json",
+        enum_name: "CharacterID",
+        keys: KeyKind::HashOfKey,
+    },
+];
+
+/// Returns the enum for a dictionary source such as `en/items.json`, if it's a hash-keyed category.
+pub fn enum_for_source(source: &str) -> Option<&'static str> {
+    let file_name = source.rsplit('/').next()?;
+
+    CATEGORIES
+        .iter()
+        .find(|category| category.file_name == file_name && matches!(category.keys, KeyKind::Hash))
+        .map(|category| category.enum_name)
+}
+
+/// Reads one data file entry as its game key, hash and text, or `None` if `language` has no text
+/// for it.
+///
+/// Per-language files map keys to `{ key, text }` objects, or to the text itself for characters.
+/// Merged files written with `--layout merged` have `{ key, text: { lang: text } }` objects.
+fn read_entry(
+    category: &Category,
+    key: &str,
+    value: &Value,
+    language: &str,
+) -> anyhow::Result<Option<(String, u32, String)>> {
+    let (game_key, text) = match value {
+        Value::Object(object) => {
+            let game_key = object.get("key").and_then(Value::as_str);
+            let text = match object.get("text") {
+                Some(Value::Object(texts)) => texts.get(language),
+                text => text,
+            };
+
+            match (game_key, text) {
+                (Some(game_key), Some(Value::String(text))) => (game_key, text),
+                (Some(_), Some(Value::Null) | None) => return Ok(None),
+                _ => anyhow::bail!("Expected {{ key, text }} for {}, found: {}", key, value),
+            }
+        }
+        Value::String(text) => (key, text),
+        _ => anyhow::bail!(
+            "Expected {{ key, text }} or text for {}, found: {}",
+            key,
+            value
+        ),
+    };
+
+    let hash = match category.keys {
+        KeyKind::Hash => parse_hash(key)
+            .filter(|_| key.len() == 8)
+            .with_context(|| format!("Expected an 8 digit hex hash, found key: {}", key))?,
+        KeyKind::HashOfKey => xxhash32_custom(key.as_bytes()),
+        KeyKind::Number => key
+            .parse::<u32>()
+            .with_context(|| format!("Expected a decimal ID, found key: {}", key))?,
+    };
+
+    Ok(Some((game_key.to_string(), hash, text.clone())))
+}
+
+/// Builds an enum for every category found in an extracted `data/{lang}` directory, or in a
+/// `data` directory of merged files, where `language` picks the text.
+///
+/// Names come from the text of each entry, so this should be given English text.
+/// Hash enums start with `Empty`, the hash of an empty string, which the game uses for unset IDs.
+pub fn load_enums(data_dir: &Path, language: &str) -> anyhow::Result<Vec<HashEnum>> {
+    let mut enums = Vec::new();
+
+    for category in &CATEGORIES {
+        let path = data_dir.join(category.file_name);
+
+        if !path.exists() {
+            eprintln!(
+                "Skipping {}, {} not found",
+                category.enum_name,
+                path.display()
+            );
+            continue;
+        }
+
+        let file = File::open(&path)
+            .with_context(|| format!("Could not open data file at path: {}", path.display()))?;
+        let json: BTreeMap<String, Value> = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Could not parse data file: {}", path.display()))?;
+
+        // Sorted by the game's key (e.g. ITEM_01_0001) so related entries stay together.
+        let mut entries = Vec::new();
+
+        for (key, value) in &json {
+            let entry = read_entry(category, key, value, language)
+                .with_context(|| format!("Could not read data file: {}", path.display()))?;
+
+            if let Some(entry) = entry.filter(|(_, _, text)| !text.is_empty()) {
+                entries.push(entry);
+            }
+        }
+
+        entries.sort();
+
+        let empty = match category.keys {
+            KeyKind::Number => None,
+            _ => Some((xxhash32_custom(b""), "Empty".to_string())),
+        };
+
+        enums.push(HashEnum::new(
+            category.enum_name,
+            empty
+                .into_iter()
+                .chain(entries.into_iter().map(|(_, hash, text)| (hash, text))),
+            Language::Imhex,
+        ));
+    }
+
+    Ok(enums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants(hash_enum: &HashEnum) -> Vec<(&str, u32)> {
+        hash_enum
+            .variants
+            .iter()
+            .map(|variant| (variant.name.as_str(), variant.hash))
+            .collect()
+    }
+
+    #[test]
+    fn loads_per_language_and_merged_data() {
+        let per_language = tempfile::tempdir().unwrap();
+        std::fs::write(
+            per_language.path().join("weapons.json"),
+            r#"{ "48110ba3": { "key": "WEP_PL1500_04_03", "text": "Ascalon" } }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            per_language.path().join("characters.json"),
+            r#"{ "Pl1500": "Vane" }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            per_language.path().join("quests.json"),
+            r#"{ "101001": { "key": "TXT_QR_101001", "text": "First Quest" } }"#,
+        )
+        .unwrap();
+
+        let merged = tempfile::tempdir().unwrap();
+        std::fs::write(
+            merged.path().join("weapons.json"),
+            r#"{
+                "48110ba3": { "key": "WEP_PL1500_04_03", "text": { "en": "Ascalon", "ja": "アスカロン" } },
+                "0000ffff": { "key": "WEP_UNUSED", "text": { "en": null, "ja": "未使用" } }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            merged.path().join("characters.json"),
+            r#"{ "Pl1500": { "key": "Pl1500", "text": { "en": "Vane", "ja": "ヴェイン" } } }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            merged.path().join("quests.json"),
+            r#"{ "101001": { "key": "TXT_QR_101001", "text": { "en": "First Quest" } } }"#,
+        )
+        .unwrap();
+
+        let empty = xxhash32_custom(b"");
+        let vane = xxhash32_custom(b"Pl1500");
+
+        for dir in [&per_language, &merged] {
+            let enums = load_enums(dir.path(), "en").unwrap();
+            let names: Vec<&str> = enums.iter().map(|e| e.name.as_str()).collect();
+
+            assert_eq!(names, ["WeaponID", "QuestID", "CharacterID"]);
+            assert_eq!(
+                variants(&enums[0]),
+                [("Empty", empty), ("Ascalon", 0x48110BA3)]
+            );
+            assert_eq!(variants(&enums[1]), [("FirstQuest", 101001)]);
+            assert_eq!(variants(&enums[2]), [("Empty", empty), ("Vane", vane)]);
+        }
+    }
+
+    #[test]
+    fn rejects_unexpected_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("weapons.json"), r#"{ "48110ba3": 3 }"#).unwrap();
+        assert!(load_enums(dir.path(), "en").is_err());
+
+        std::fs::write(
+            dir.path().join("weapons.json"),
+            r#"{ "WEP_PL1500": { "key": "WEP_PL1500", "text": "Ascalon" } }"#,
+        )
+        .unwrap();
+        assert!(load_enums(dir.path(), "en").is_err());
+
+        std::fs::remove_file(dir.path().join("weapons.json")).unwrap();
+        std::fs::write(
+            dir.path().join("quests.json"),
+            r#"{ "QR_1": { "key": "TXT_QR_QR_1", "text": "Quest" } }"#,
+        )
+        .unwrap();
+        assert!(load_enums(dir.path(), "en").is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use xxhash32_lib::xxhash32_custom;
+
+use crate::dictionary::{parse_hash, Dictionary};
+use crate::output::{DictionaryWriter, HashFormat, OutputFormat};
+
+/// Name list formats used by other tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExchangeFormat {
+    /// Our `hash,value` CSV.
+    Csv,
+    /// One unhashed name per line.
+    Wordlist,
+    /// A JSON object of hash to name (or array of names), e.g. `{ "0x48110BA3": "WEP_PL1500_04_03" }`.
+    JsonMap,
+}
+
+/// What to do when an imported name has a hash that already has a different name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Keep the existing name.
+    #[default]
+    Keep,
+    /// Replace the existing names with the imported one.
+    Replace,
+    /// Keep both, as xxhash32 collisions are common.
+    Both,
+}
+
+/// Names by hash, sorted so merged dictionaries diff cleanly.
+pub type Names = BTreeMap<u32, Vec<String>>;
+
+pub fn from_dictionary(dictionary: &Dictionary) -> Names {
+    let mut names = Names::new();
+
+    for (hash, entries) in dictionary.iter() {
+        let values = names.entry(hash).or_default();
+
+        for entry in entries.iter() {
+            if !values.contains(&entry.value) {
+                values.push(entry.value.clone());
+            }
+        }
+    }
+
+    for values in names.values_mut() {
+        values.sort();
+    }
+
+    names
+}
+
+/// Reads names from another tool's format.
+///
+/// Names whose stated hash doesn't match their xxhash32 are reported and skipped, as they're
+/// usually from a different hash function or a typo.
+pub fn read(path: &Path, format: ExchangeFormat) -> anyhow::Result<Vec<(u32, String)>> {
+    let file = File::open(path)
+        .with_context(|| format!("Could not open file at path: {}", path.display()))?;
+    let reader = BufReader::new(file);
+
+    let entries: Vec<(Option<u32>, String)> = match format {
+        ExchangeFormat::Csv => {
+            let mut dictionary = Dictionary::default();
+            dictionary.load_delimited(path, b',')?;
+
+            from_dictionary(&dictionary)
+                .into_iter()
+                .flat_map(|(hash, values)| values.into_iter().map(move |value| (Some(hash), value)))
+                .collect()
+        }
+        ExchangeFormat::Wordlist => reader
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|line| !line.is_empty())
+            .map(|line| (None, line))
+            .collect(),
+        ExchangeFormat::JsonMap => {
+            let json: Map<String, Value> = serde_json::from_reader(reader)
+                .with_context(|| format!("Could not parse JSON map: {}", path.display()))?;
+            let mut entries = Vec::new();
+
+            for (key, value) in json {
+                let hash = parse_hash(&key);
+                let values = match value {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                };
+
+                for value in values {
+                    let name = match &value {
+                        Value::String(name) => Some(name.as_str()),
+                        Value::Object(object) => object
+                            .get("value")
+                            .or_else(|| object.get("key"))
+                            .and_then(Value::as_str),
+                        _ => None,
+                    };
+
+                    match name {
+                        Some(name) => entries.push((hash, name.to_string())),
+                        None => eprintln!("Skipping {}: expected a name", key),
+                    }
+                }
+            }
+
+            entries
+        }
+    };
+
+    let mut verified = Vec::with_capacity(entries.len());
+
+    for (stated, value) in entries {
+        let hash = xxhash32_custom(value.as_bytes());
+
+        match stated {
+            Some(stated) if stated != hash => eprintln!(
+                "Skipping {}: listed as {:#010X} but hashes to {:#010X}",
+                value, stated, hash
+            ),
+            _ => verified.push((hash, value)),
+        }
+    }
+
+    Ok(verified)
+}
+
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    pub added: usize,
+    pub duplicates: usize,
+    /// Hash, existing names and the imported name.
+    pub conflicts: Vec<(u32, Vec<String>, String)>,
+}
+
+impl MergeReport {
+    pub fn print(&self, policy: ConflictPolicy) {
+        for (hash, existing, incoming) in &self.conflicts {
+            eprintln!(
+                "Conflict {:#010X}: existing {} vs imported {}",
+                hash,
+                existing.join(" | "),
+                incoming
+            );
+        }
+
+        eprintln!(
+            "Added {} names, skipped {} duplicates, {} conflicts ({})",
+            self.added,
+            self.duplicates,
+            self.conflicts.len(),
+            match policy {
+                ConflictPolicy::Keep => "kept existing",
+                ConflictPolicy::Replace => "replaced existing",
+                ConflictPolicy::Both => "kept both",
+            }
+        );
+    }
+}
+
+pub fn merge(
+    names: &mut Names,
+    incoming: impl IntoIterator<Item = (u32, String)>,
+    policy: ConflictPolicy,
+) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    for (hash, value) in incoming {
+        let values = names.entry(hash).or_default();
+
+        if values.contains(&value) {
+            report.duplicates += 1;
+            continue;
+        }
+
+        if values.is_empty() {
+            values.push(value);
+            report.added += 1;
+            continue;
+        }
+
+        report.conflicts.push((hash, values.clone(), value.clone()));
+
+        match policy {
+            ConflictPolicy::Keep => {}
+            ConflictPolicy::Replace => *values = vec![value],
+            ConflictPolicy::Both => {
+                values.push(value);
+                values.sort();
+            }
+        }
+    }
+
+    report
+}
+
+/// Writes names in another tool's format.
+pub fn write(output: Box<dyn Write>, names: &Names, format: ExchangeFormat) -> anyhow::Result<()> {
+    match format {
+        ExchangeFormat::Csv => {
+            let mut writer = DictionaryWriter::new(output, OutputFormat::Csv, HashFormat::Upper)?;
+
+            for (hash, values) in names {
+                for value in values {
+                    writer.write(*hash, value)?;
+                }
+            }
+
+            writer.finish()?;
+        }
+        ExchangeFormat::Wordlist => {
+            let mut output = output;
+
+            for value in names.values().flatten() {
+                writeln!(output, "{}", value)?;
+            }
+
+            output.flush()?;
+        }
+        ExchangeFormat::JsonMap => {
+            let mut output = output;
+            let map: Map<String, Value> = names
+                .iter()
+                .map(|(hash, values)| {
+                    let value = match values.as_slice() {
+                        [value] => Value::String(value.clone()),
+                        values => values.iter().cloned().map(Value::String).collect(),
+                    };
+
+                    (HashFormat::Upper.format(*hash), value)
+                })
+                .collect();
+
+            serde_json::to_writer_pretty(&mut output, &map)?;
+            writeln!(output)?;
+            output.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_with_conflict_policy() {
+        // Two names that really collide under xxhash32_custom.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.txt");
+        std::fs::write(&path, "WEP_IAROATYVMX\nWEP_OPTV_43DIKQZ\nhello\n").unwrap();
+
+        let incoming = read(&path, ExchangeFormat::Wordlist).unwrap();
+        assert_eq!(incoming[0].0, 0x257801C0);
+        assert_eq!(incoming[1].0, 0x257801C0);
+
+        let mut names = Names::new();
+        names.insert(0x257801C0, vec!["WEP_IAROATYVMX".to_string()]);
+
+        let report = merge(&mut names.clone(), incoming.clone(), ConflictPolicy::Keep);
+        assert_eq!(report.added, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(
+            report.conflicts,
+            [(
+                0x257801C0,
+                vec!["WEP_IAROATYVMX".to_string()],
+                "WEP_OPTV_43DIKQZ".to_string()
+            )]
+        );
+
+        let mut replaced = names.clone();
+        merge(&mut replaced, incoming.clone(), ConflictPolicy::Replace);
+        assert_eq!(replaced[&0x257801C0], ["WEP_OPTV_43DIKQZ"]);
+
+        merge(&mut names, incoming, ConflictPolicy::Both);
+        assert_eq!(names[&0x257801C0], ["WEP_IAROATYVMX", "WEP_OPTV_43DIKQZ"]);
+    }
+}
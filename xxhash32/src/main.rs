@@ -1,11 +1,30 @@
+mod annotate;
+mod codegen;
+mod coverage;
+mod dictionary;
+mod hash_file;
+mod imhex;
+mod interop;
+mod output;
+mod repl;
+mod scan;
+mod strings;
+
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-use clap::{Parser, Subcommand};
+use annotate::{AnnotateFormat, Annotator, Json};
+use anyhow::Context;
+use clap::{Args, Parser, Subcommand};
+use codegen::HashEnum;
+use dictionary::{parse_hash, Dictionary};
+use hash_file::HashFileOptions;
 use libbruteforce::{
     symbols, BasicCrackParameter, CrackParameter, TargetHashAndHashFunction, TargetHashInput,
 };
+use output::{DictionaryWriter, HashFormat, OutputFormat};
+use xxhash32_lib::index::HashIndexBuilder;
 use xxhash32_lib::xxhash32_custom;
 
 #[derive(Parser)]
@@ -20,35 +39,331 @@ enum Commands {
     /// Hashes a single string.
     Hash { value: String },
 
-    /// Hashes each line of a file and writes the results to a CSV output file.
-    HashFile { file: PathBuf },
+    /// Hashes each line of a file and writes `hash,value` pairs to a file or stdout.
+    HashFile {
+        /// Input wordlist, or `-` to read from stdin.
+        file: PathBuf,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Skip lines that have already been written. Uses 8 bytes of memory per unique line.
+        #[arg(long)]
+        dedupe: bool,
+
+        /// Number of hashing threads, defaults to the number of CPUs.
+        #[arg(short = 'j', long)]
+        threads: Option<usize>,
+
+        /// Number of lines hashed by a thread at a time.
+        #[arg(long, default_value_t = 65536)]
+        chunk_size: usize,
+
+        /// Write lines as soon as they're hashed instead of in input order.
+        #[arg(long)]
+        unordered: bool,
+    },
 
     /// Brute forces a hash, trying to find a string up to a given length that hashes to the given hash.
     BruteForce { hash: String, length: u32 },
+
+    /// Extracts printable strings from a file, hashes each one and writes a deduplicated CSV dictionary.
+    Strings {
+        file: PathBuf,
+
+        #[command(flatten)]
+        output: OutputArgs,
+
+        /// Minimum number of characters for a string to be included.
+        #[arg(long, default_value_t = 4)]
+        min_length: usize,
+
+        /// Maximum number of characters for a string to be included.
+        #[arg(long, default_value_t = 256)]
+        max_length: usize,
+
+        /// Skip scanning for UTF-16LE strings.
+        #[arg(long)]
+        no_utf16: bool,
+
+        /// Split strings on anything that isn't [A-Za-z0-9_], keeping only identifier-like pieces.
+        #[arg(long)]
+        identifiers_only: bool,
+    },
+
+    /// Looks up the names of one or more hashes in the given dictionaries. Reads hashes from stdin if none are given or `-`.
+    ///
+    /// Exits with a non-zero status if any hash could not be resolved.
+    Lookup {
+        hashes: Vec<String>,
+
+        /// A `hash,value` CSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(short, long = "dictionary", required = true)]
+        dictionaries: Vec<PathBuf>,
+    },
+
+    /// Names the hashes in a JSON, CSV or text dump, keeping it valid in its original format.
+    Annotate {
+        /// The file to annotate, `-` reads from stdin.
+        file: PathBuf,
+
+        /// A `hash,value` CSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(short, long = "dictionary", required = true)]
+        dictionaries: Vec<PathBuf>,
+
+        /// Input format, inferred from the file extension by default.
+        #[arg(short, long)]
+        format: Option<AnnotateFormat>,
+
+        /// Replace hashes with their names instead of adding a `_name` field, column or suffix.
+        #[arg(long)]
+        replace: bool,
+
+        /// Also match decimal u32 numbers, not just 8-digit hex tokens.
+        #[arg(long)]
+        numbers: bool,
+
+        /// The CSV has no header row.
+        #[arg(long)]
+        no_headers: bool,
+
+        /// Output file, `-` writes to stdout.
+        #[arg(short, long, default_value = "-")]
+        output: PathBuf,
+    },
+
+    /// Generates an enum of every hash in a dictionary, named after the dictionary's values or text.
+    Codegen {
+        /// A `hash,value` dictionary or an extracted data/{lang}/*.json file. Can be repeated.
+        #[arg(required = true)]
+        dictionaries: Vec<PathBuf>,
+
+        /// Name of the generated enum.
+        #[arg(short, long)]
+        name: String,
+
+        #[arg(short, long, value_enum, default_value_t = codegen::Language::Rust)]
+        language: codegen::Language,
+
+        /// Namespace to wrap C# and ImHex enums in.
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Output file, defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Finds every little-endian u32 in a binary that matches a dictionary hash.
+    Scan {
+        file: PathBuf,
+
+        /// A `hash,value` CSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(short, long = "dictionary", required = true)]
+        dictionaries: Vec<PathBuf>,
+
+        /// Only check offsets that are a multiple of this.
+        #[arg(long, default_value_t = 4)]
+        alignment: usize,
+
+        /// Write matches as an ImHex bookmark file (.hexbm).
+        #[arg(long)]
+        bookmarks: Option<PathBuf>,
+
+        /// Write an ImHex pattern placing a typed u32 at every match.
+        #[arg(long)]
+        hexpat: Option<PathBuf>,
+
+        /// Namespace of the enum.pat include used by --hexpat.
+        #[arg(long, default_value = "gbfr")]
+        namespace: String,
+    },
+
+    /// Starts an interactive session for hashing guesses with dictionaries kept loaded.
+    Repl {
+        /// A `hash,value` CSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(short, long = "dictionary")]
+        dictionaries: Vec<PathBuf>,
+
+        /// Hashes to watch for, or files with one hash per line. Can be repeated.
+        #[arg(short, long = "target")]
+        targets: Vec<String>,
+
+        /// History file, defaults to ~/.xxhash32_history.
+        #[arg(long)]
+        history: Option<PathBuf>,
+    },
+
+    /// Reports how many observed hashes a dictionary can name, grouped by source and name prefix.
+    Coverage {
+        /// A `hash,value` CSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(short, long = "dictionary", required = true)]
+        dictionaries: Vec<PathBuf>,
+
+        /// Files with one observed hash per line. Can be repeated.
+        #[arg(long = "hashes")]
+        hash_lists: Vec<PathBuf>,
+
+        /// Output of `scan`, whose second column is the observed hash. Can be repeated.
+        #[arg(long = "scan-results")]
+        scan_results: Vec<PathBuf>,
+
+        /// A .msg file or a directory of them, whose id_hash_ values are observed. Unhashed keys
+        /// count as resolved. Can be repeated.
+        #[arg(long = "msg")]
+        msg_files: Vec<PathBuf>,
+
+        /// Sqlite database to read observed hashes from, e.g. system_table.sqlite.
+        #[arg(long, requires = "columns")]
+        sqlite: Option<PathBuf>,
+
+        /// Database column holding hashes, as table.column. Can be repeated.
+        #[arg(long = "column", requires = "sqlite")]
+        columns: Vec<String>,
+
+        /// Write unresolved hashes, one per line, for brute forcing or `repl --target`.
+        #[arg(long)]
+        unresolved_output: Option<PathBuf>,
+
+        /// Only print the summary, without listing unresolved hashes.
+        #[arg(long)]
+        summary: bool,
+    },
+
+    /// Merges a name list from another tool into a `hash,value` CSV dictionary, reporting conflicts.
+    Import {
+        file: PathBuf,
+
+        #[arg(long, value_enum)]
+        from: interop::ExchangeFormat,
+
+        /// Dictionary to merge into, created if it doesn't exist.
+        #[arg(long)]
+        into: PathBuf,
+
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: interop::ConflictPolicy,
+    },
+
+    /// Converts dictionaries into a name list for another tool.
+    Export {
+        /// A `hash,value` CSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(required = true)]
+        dictionaries: Vec<PathBuf>,
+
+        #[arg(long, value_enum)]
+        to: interop::ExchangeFormat,
+
+        /// Output file, defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Builds a compact, memory-mapped index from dictionaries, for instant lookups in huge name lists.
+    ///
+    /// Indexes (.idx) can be passed anywhere a dictionary is accepted, and to `language-unpack print --names`.
+    BuildIndex {
+        /// A `hash,value` CSV/TSV, an extracted data/{lang}/*.json file or a directory of them. Can be repeated.
+        #[arg(required = true)]
+        dictionaries: Vec<PathBuf>,
+
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Generates the ImHex enum.pat include from the English data written by `language-unpack extract-all`.
+    ImhexEnums {
+        /// Directory containing the extracted English JSON files, or merged files written with
+        /// `--layout merged`.
+        #[arg(long, default_value = "data/en")]
+        data: PathBuf,
+
+        /// Language whose text names the variants when reading merged files.
+        #[arg(long, default_value = "en")]
+        lang: String,
+
+        #[arg(long, default_value = "gbfr")]
+        namespace: String,
+
+        /// Output file, e.g. imhex/includes/gbfr/enum.pat. Defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// Output file, or `-` for stdout. Defaults to the input file name with the format's extension appended.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    format: OutputFormat,
+
+    #[arg(long, value_enum, default_value_t)]
+    hash_format: HashFormat,
 }
 
-fn main() {
+impl OutputArgs {
+    /// Resolves the output path for an input file, writing to stdout when reading from stdin.
+    fn path(&self, input: &Path) -> PathBuf {
+        match &self.output {
+            Some(output) => output.clone(),
+            None if input == Path::new("-") => PathBuf::from("-"),
+            None => {
+                let filename = input.file_stem().unwrap_or(input.as_os_str());
+                PathBuf::from(format!(
+                    "{}.{}",
+                    filename.to_string_lossy(),
+                    self.format.extension()
+                ))
+            }
+        }
+    }
+
+    fn writer(&self, input: &Path) -> anyhow::Result<DictionaryWriter> {
+        DictionaryWriter::create(&self.path(input), self.format, self.hash_format)
+    }
+}
+
+fn main() -> anyhow::Result<()> {
     let args = Cli::parse();
 
     match &args.command {
         Commands::Hash { value } => {
             println!("{:#010X}", xxhash32_custom(value.as_bytes()));
         }
-        Commands::HashFile { file: file_path } => {
-            let file = File::open(file_path.clone()).expect("file not found");
-            let file_reader = BufReader::new(file);
+        Commands::HashFile {
+            file: file_path,
+            output,
+            dedupe,
+            threads,
+            chunk_size,
+            unordered,
+        } => {
+            let file_reader: Box<dyn BufRead + Send> = if file_path == Path::new("-") {
+                Box::new(BufReader::new(std::io::stdin()))
+            } else {
+                let file = File::open(file_path).with_context(|| {
+                    format!("Could not open file at path: {}", file_path.display())
+                })?;
+                Box::new(BufReader::with_capacity(1024 * 1024, file))
+            };
 
-            // Sets output to the file name with .csv appended.
-            let filename = file_path.file_stem().expect("file name not found");
-            let output_filename = format!("{}.csv", filename.to_str().unwrap());
-            let mut output = File::create(output_filename).expect("could not create output file");
+            let options = HashFileOptions {
+                threads: threads.unwrap_or_else(|| {
+                    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+                }),
+                chunk_size: *chunk_size,
+                ordered: !unordered,
+                dedupe: *dedupe,
+            };
 
-            writeln!(output, "hash,value").unwrap();
+            let mut writer = output.writer(file_path)?;
+            let stats = hash_file::hash_lines(file_reader, &mut writer, &options)?;
+            writer.finish()?;
 
-            for line in file_reader.lines().map(|l| l.unwrap()) {
-                let hash = xxhash32_custom(line.as_bytes());
-                writeln!(output, "{:#010X},{}", hash, line).unwrap();
-            }
+            stats.report();
         }
         Commands::BruteForce { hash, length } => {
             // Lazy, so I'm using libbruteforce to brute force the hash.
@@ -81,7 +396,360 @@ fn main() {
                 println!("No solution found");
             }
         }
+        Commands::Strings {
+            file,
+            output,
+            min_length,
+            max_length,
+            no_utf16,
+            identifiers_only,
+        } => {
+            let data = std::fs::read(file)
+                .with_context(|| format!("Could not read file at path: {}", file.display()))?;
+
+            let options = strings::StringsOptions {
+                min_length: *min_length,
+                max_length: *max_length,
+                utf16: !no_utf16,
+                identifiers_only: *identifiers_only,
+            };
+
+            let values = strings::extract_strings(&data, &options);
+            let mut writer = output.writer(file)?;
+
+            for value in &values {
+                writer.write(xxhash32_custom(value.as_bytes()), value)?;
+            }
+
+            writer.finish()?;
+
+            eprintln!(
+                "Wrote {} strings to {}",
+                values.len(),
+                output.path(file).display()
+            );
+        }
+        Commands::Lookup {
+            hashes,
+            dictionaries,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let hashes = if hashes.is_empty() || hashes == &["-"] {
+                std::io::stdin()
+                    .lines()
+                    .collect::<Result<Vec<_>, _>>()
+                    .context("Could not read hashes from stdin")?
+            } else {
+                hashes.clone()
+            };
+
+            let mut unresolved = 0;
+
+            for input in hashes.iter().map(|h| h.trim()).filter(|h| !h.is_empty()) {
+                let Some(hash) = parse_hash(input) else {
+                    eprintln!("Invalid hash: {}", input);
+                    unresolved += 1;
+                    continue;
+                };
+
+                let entries = dictionary.get(hash);
+
+                if entries.is_empty() {
+                    println!("{:#010X} (unresolved)", hash);
+                    unresolved += 1;
+                    continue;
+                }
+
+                for entry in entries.iter() {
+                    match &entry.text {
+                        Some(text) => println!(
+                            "{:#010X} {} [{}] {:?}",
+                            hash, entry.value, entry.source, text
+                        ),
+                        None => println!("{:#010X} {} [{}]", hash, entry.value, entry.source),
+                    }
+                }
+            }
+
+            if unresolved > 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Annotate {
+            file,
+            dictionaries,
+            format,
+            replace,
+            numbers,
+            no_headers,
+            output,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let input = if file == Path::new("-") {
+                let mut input = Vec::new();
+                std::io::Read::read_to_end(&mut std::io::stdin(), &mut input)
+                    .context("Could not read stdin")?;
+                input
+            } else {
+                std::fs::read(file)
+                    .with_context(|| format!("Could not read file at path: {}", file.display()))?
+            };
+
+            let format = format.unwrap_or_else(|| {
+                AnnotateFormat::from_extension(dictionary::extension(file).as_deref())
+            });
+
+            let mut annotator = Annotator::new(&dictionary);
+            annotator.replace = *replace;
+            annotator.numbers = *numbers;
+
+            let mut writer = output::open_output(output)?;
+
+            match format {
+                AnnotateFormat::Json => {
+                    let mut value: Json = serde_json::from_slice(&input)
+                        .with_context(|| format!("Could not parse JSON: {}", file.display()))?;
+                    annotator.json(&mut value);
+                    serde_json::to_writer_pretty(&mut writer, &value)?;
+                    writeln!(writer)?;
+                }
+                AnnotateFormat::Csv | AnnotateFormat::Tsv => {
+                    let delimiter = if format == AnnotateFormat::Csv {
+                        b','
+                    } else {
+                        b'\t'
+                    };
+                    annotator.delimited(&input, delimiter, !no_headers, &mut writer)?;
+                }
+                AnnotateFormat::Text => {
+                    let text = String::from_utf8_lossy(&input);
+                    writer.write_all(annotator.text(&text).as_bytes())?;
+                }
+            }
+
+            writer.flush()?;
+            eprintln!("Resolved {} hashes", annotator.resolved);
+        }
+        Commands::Codegen {
+            dictionaries,
+            name,
+            language,
+            namespace,
+            output,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let hash_enum = HashEnum::from_dictionary(name, &dictionary, *language);
+            let code = hash_enum.generate(*language, namespace.as_deref());
+
+            let mut output = output::open_output(output.as_deref().unwrap_or(Path::new("-")))?;
+            output.write_all(code.as_bytes())?;
+            output.flush()?;
+        }
+        Commands::Scan {
+            file,
+            dictionaries,
+            alignment,
+            bookmarks,
+            hexpat,
+            namespace,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let data = std::fs::read(file)
+                .with_context(|| format!("Could not read file at path: {}", file.display()))?;
+            let matches = scan::scan(&data, &dictionary, *alignment);
+
+            for m in &matches {
+                println!("{:#010X} {:#010X} {}", m.offset, m.hash, m.name());
+            }
+
+            if let Some(path) = bookmarks {
+                std::fs::write(path, scan::to_bookmarks(&matches))
+                    .with_context(|| format!("Could not write bookmarks to: {}", path.display()))?;
+            }
+
+            if let Some(path) = hexpat {
+                std::fs::write(path, scan::to_hexpat(&matches, namespace))
+                    .with_context(|| format!("Could not write pattern to: {}", path.display()))?;
+            }
+
+            eprintln!("Found {} matches", matches.len());
+        }
+        Commands::Repl {
+            dictionaries,
+            targets,
+            history,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let mut repl = repl::Repl::new(dictionary);
+
+            for target in targets {
+                repl.add_targets(target)?;
+            }
+
+            let history = history.clone().or_else(repl::default_history_path);
+            repl.run(history.as_deref())?;
+        }
+        Commands::Coverage {
+            dictionaries,
+            hash_lists,
+            scan_results,
+            msg_files,
+            sqlite,
+            columns,
+            unresolved_output,
+            summary,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let mut observed = coverage::Observed::default();
+
+            for path in hash_lists {
+                observed.load_hash_list(path)?;
+            }
+
+            for path in scan_results {
+                observed.load_scan_results(path)?;
+            }
+
+            for path in msg_files {
+                observed.load_msg(path)?;
+            }
+
+            if let Some(sqlite) = sqlite {
+                for column in columns {
+                    observed.load_sqlite(sqlite, column)?;
+                }
+            }
+
+            let report = coverage::CoverageReport::new(&observed, &dictionary);
+            report.print(!summary);
+
+            if let Some(path) = unresolved_output {
+                report.write_targets(&mut output::open_output(path)?)?;
+            }
+        }
+        Commands::Import {
+            file,
+            from,
+            into,
+            on_conflict,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            if into.exists() {
+                dictionary.load_delimited(into, b',')?;
+            }
+
+            let mut names = interop::from_dictionary(&dictionary);
+            let incoming = interop::read(file, *from)?;
+            let report = interop::merge(&mut names, incoming, *on_conflict);
+
+            interop::write(
+                output::open_output(into)?,
+                &names,
+                interop::ExchangeFormat::Csv,
+            )?;
+            report.print(*on_conflict);
+        }
+        Commands::Export {
+            dictionaries,
+            to,
+            output,
+        } => {
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                dictionary.load(path)?;
+            }
+
+            let names = interop::from_dictionary(&dictionary);
+            interop::write(
+                output::open_output(output.as_deref().unwrap_or(Path::new("-")))?,
+                &names,
+                *to,
+            )?;
+        }
+        Commands::BuildIndex {
+            dictionaries,
+            output,
+        } => {
+            let mut builder = HashIndexBuilder::new();
+            let mut dictionary = Dictionary::default();
+
+            for path in dictionaries {
+                // Delimited files are streamed straight into the index, as they can be huge.
+                match dictionary::extension(path).as_deref() {
+                    Some("csv") if path.is_file() => {
+                        dictionary::read_delimited(path, b',', |hash, value| {
+                            builder.insert(hash, &value)
+                        })?
+                    }
+                    Some("tsv") if path.is_file() => {
+                        dictionary::read_delimited(path, b'\t', |hash, value| {
+                            builder.insert(hash, &value)
+                        })?
+                    }
+                    _ => dictionary.load(path)?,
+                }
+            }
+
+            for (hash, entries) in dictionary.iter() {
+                for entry in entries.iter() {
+                    builder.insert(hash, &entry.value);
+                }
+            }
+
+            let file = File::create(output)
+                .with_context(|| format!("Could not create output file: {}", output.display()))?;
+            builder.write(BufWriter::new(file))?;
+
+            eprintln!("Wrote {} hashes to {}", builder.len(), output.display());
+        }
+        Commands::ImhexEnums {
+            data,
+            lang,
+            namespace,
+            output,
+        } => {
+            let enums = imhex::load_enums(data, lang)?;
+            let pattern = codegen::imhex_pattern(&enums, Some(namespace));
+
+            let mut output = output::open_output(output.as_deref().unwrap_or(Path::new("-")))?;
+            output.write_all(pattern.as_bytes())?;
+            output.flush()?;
+        }
     }
+
+    Ok(())
 }
 
 fn do_hash(input: &str) -> u32 {
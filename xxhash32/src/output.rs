@@ -0,0 +1,185 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use serde_json::json;
+
+/// File format used when writing `hash,value` dictionaries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    Tsv,
+    /// A single JSON array of `{ "hash", "value" }` objects.
+    Json,
+    /// One `{ "hash", "value" }` JSON object per line.
+    Ndjson,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+/// How hashes are written out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum HashFormat {
+    /// 0x48110BA3
+    #[default]
+    Upper,
+    /// 0x48110ba3
+    Lower,
+    /// 48110ba3, as used for keys in extracted data/{lang}/*.json files.
+    Bare,
+}
+
+impl HashFormat {
+    pub fn format(&self, hash: u32) -> String {
+        match self {
+            HashFormat::Upper => format!("{:#010X}", hash),
+            HashFormat::Lower => format!("{:#010x}", hash),
+            HashFormat::Bare => format!("{:08x}", hash),
+        }
+    }
+}
+
+enum Sink {
+    Delimited(Box<csv::Writer<Box<dyn Write>>>),
+    Json { output: Box<dyn Write>, first: bool },
+    Ndjson(Box<dyn Write>),
+}
+
+/// Streams `hash,value` pairs to a file or stdout in any of the supported formats.
+pub struct DictionaryWriter {
+    sink: Sink,
+    hash_format: HashFormat,
+}
+
+impl DictionaryWriter {
+    pub fn new(
+        output: Box<dyn Write>,
+        format: OutputFormat,
+        hash_format: HashFormat,
+    ) -> anyhow::Result<Self> {
+        let sink = match format {
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let delimiter = if format == OutputFormat::Tsv {
+                    b'\t'
+                } else {
+                    b','
+                };
+                let mut writer = csv::WriterBuilder::new()
+                    .delimiter(delimiter)
+                    .from_writer(output);
+                writer.write_record(["hash", "value"])?;
+                Sink::Delimited(Box::new(writer))
+            }
+            OutputFormat::Json => {
+                let mut output = output;
+                write!(output, "[")?;
+                Sink::Json {
+                    output,
+                    first: true,
+                }
+            }
+            OutputFormat::Ndjson => Sink::Ndjson(output),
+        };
+
+        Ok(Self { sink, hash_format })
+    }
+
+    /// Creates a writer for the given path, where `-` means stdout.
+    pub fn create(
+        path: &Path,
+        format: OutputFormat,
+        hash_format: HashFormat,
+    ) -> anyhow::Result<Self> {
+        Self::new(open_output(path)?, format, hash_format)
+    }
+
+    pub fn write(&mut self, hash: u32, value: &str) -> anyhow::Result<()> {
+        let hash = self.hash_format.format(hash);
+
+        match &mut self.sink {
+            Sink::Delimited(writer) => writer.write_record([hash.as_str(), value])?,
+            Sink::Json { output, first } => {
+                let separator = if *first { "" } else { "," };
+                *first = false;
+                write!(
+                    output,
+                    "{}\n  {}",
+                    separator,
+                    json!({ "hash": hash, "value": value })
+                )?;
+            }
+            Sink::Ndjson(output) => {
+                writeln!(output, "{}", json!({ "hash": hash, "value": value }))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> anyhow::Result<()> {
+        match self.sink {
+            Sink::Delimited(mut writer) => writer.flush()?,
+            Sink::Json { mut output, first } => {
+                if first {
+                    writeln!(output, "]")?;
+                } else {
+                    writeln!(output, "\n]")?;
+                }
+                output.flush()?;
+            }
+            Sink::Ndjson(mut output) => output.flush()?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens a buffered output file, where `-` means stdout.
+pub fn open_output(path: &Path) -> anyhow::Result<Box<dyn Write>> {
+    if path == Path::new("-") {
+        return Ok(Box::new(BufWriter::new(std::io::stdout().lock())));
+    }
+
+    let file = File::create(path)
+        .with_context(|| format!("Could not create output file: {}", path.display()))?;
+
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_hashes() {
+        assert_eq!(HashFormat::Upper.format(0x48110BA3), "0x48110BA3");
+        assert_eq!(HashFormat::Lower.format(0x48110BA3), "0x48110ba3");
+        assert_eq!(HashFormat::Bare.format(0x0000FFFF), "0000ffff");
+    }
+
+    #[test]
+    fn quotes_csv_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hashes.csv");
+        let mut writer =
+            DictionaryWriter::create(&path, OutputFormat::Csv, HashFormat::Upper).unwrap();
+        writer.write(0x48110BA3, "a,b").unwrap();
+        writer.finish().unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        assert_eq!(written, "hash,value\n0x48110BA3,\"a,b\"\n");
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use xxhash32_lib::xxhash32_custom;
+
+use crate::dictionary::{parse_hash, Dictionary};
+use crate::output::{DictionaryWriter, HashFormat, OutputFormat};
+
+const HELP: &str = "\
+Type a string to hash it, or a 0x-prefixed hash to look it up in the loaded dictionaries.
+
+Commands:
+  :load <path>           Loads a dictionary file or directory
+  :save <path>           Saves every string hashed this session as a hash,value CSV
+  :target [hash..|path]  Watches hashes (or a file with one per line), lists them without arguments
+  :help                  Prints this message
+  :quit                  Exits";
+
+/// Interactive session that keeps dictionaries loaded between guesses.
+pub struct Repl {
+    dictionary: Dictionary,
+    /// Watched hashes, with the first guess that matched each one.
+    targets: BTreeMap<u32, Option<String>>,
+    /// Every string hashed this session, by hash.
+    guesses: BTreeMap<u32, Vec<String>>,
+}
+
+impl Repl {
+    pub fn new(dictionary: Dictionary) -> Self {
+        Self {
+            dictionary,
+            targets: BTreeMap::new(),
+            guesses: BTreeMap::new(),
+        }
+    }
+
+    /// Runs the read-eval-print loop until EOF or `:quit`, keeping line history in `history`.
+    pub fn run(&mut self, history: Option<&Path>) -> anyhow::Result<()> {
+        let mut editor = DefaultEditor::new()?;
+
+        if let Some(history) = history {
+            // A missing history file just means this is the first session.
+            let _ = editor.load_history(history);
+        }
+
+        println!("Type :help for commands.");
+
+        loop {
+            let line = match editor.readline("xxhash32> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            editor.add_history_entry(line.as_str())?;
+
+            if line.trim() == ":quit" || line.trim() == ":q" {
+                break;
+            }
+
+            if let Err(error) = self.eval(&line) {
+                println!("Error: {:#}", error);
+            }
+        }
+
+        if let Some(history) = history {
+            editor.save_history(history)?;
+        }
+
+        Ok(())
+    }
+
+    fn eval(&mut self, line: &str) -> anyhow::Result<()> {
+        let trimmed = line.trim();
+
+        let Some(command) = trimmed.strip_prefix(':') else {
+            if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+                if let Some(hash) = parse_hash(trimmed) {
+                    self.lookup(hash);
+                    return Ok(());
+                }
+            }
+
+            // Guesses are hashed exactly as typed, only the line ending is stripped.
+            self.guess(line);
+            return Ok(());
+        };
+
+        let (name, rest) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        // Paths are the rest of the line, so they can contain spaces.
+        let path = rest.trim();
+
+        match name {
+            "load" => {
+                anyhow::ensure!(!path.is_empty(), "Usage: :load <path>");
+                self.dictionary.load(Path::new(path))?;
+                println!("Loaded {}", path);
+            }
+            "save" => {
+                anyhow::ensure!(!path.is_empty(), "Usage: :save <path>");
+                self.save(Path::new(path))?;
+            }
+            "target" => {
+                if path.is_empty() {
+                    self.print_targets();
+                } else if Path::new(path).exists() {
+                    self.add_targets(path)?;
+                } else {
+                    for argument in path.split_whitespace() {
+                        self.add_targets(argument)?;
+                    }
+                }
+            }
+            "help" => println!("{}", HELP),
+            other => println!("Unknown command :{}, type :help for commands.", other),
+        }
+
+        Ok(())
+    }
+
+    fn guess(&mut self, value: &str) {
+        let hash = xxhash32_custom(value.as_bytes());
+        println!("{:#010X}", hash);
+
+        let guesses = self.guesses.entry(hash).or_default();
+        if !guesses.iter().any(|guess| guess == value) {
+            guesses.push(value.to_string());
+        }
+
+        if let Some(matched) = self.targets.get_mut(&hash) {
+            println!("  Matches target {:#010X}!", hash);
+            matched.get_or_insert_with(|| value.to_string());
+        }
+
+        for entry in self.dictionary.get(hash).iter() {
+            println!("  Known as {} [{}]", entry.value, entry.source);
+        }
+    }
+
+    fn lookup(&self, hash: u32) {
+        let entries = self.dictionary.get(hash);
+
+        if entries.is_empty() {
+            println!("{:#010X} (unresolved)", hash);
+        }
+
+        for entry in entries.iter() {
+            match &entry.text {
+                Some(text) => println!("{} [{}] {:?}", entry.value, entry.source, text),
+                None => println!("{} [{}]", entry.value, entry.source),
+            }
+        }
+
+        for guess in self.guesses.get(&hash).into_iter().flatten() {
+            println!("{} [session]", guess);
+        }
+    }
+
+    /// Watches a hash, or every hash in a file with one per line. An existing file wins over a
+    /// name that's also valid hex, such as `beef`.
+    pub fn add_targets(&mut self, argument: &str) -> anyhow::Result<()> {
+        if !Path::new(argument).exists() {
+            if let Some(hash) = parse_hash(argument) {
+                self.add_target(hash);
+                return Ok(());
+            }
+        }
+
+        let contents = std::fs::read_to_string(argument)
+            .with_context(|| format!("Could not read targets from: {}", argument))?;
+        let before = self.targets.len();
+
+        for hash in contents.lines().filter_map(parse_hash) {
+            self.add_target(hash);
+        }
+
+        println!(
+            "Watching {} new targets from {}",
+            self.targets.len() - before,
+            argument
+        );
+
+        Ok(())
+    }
+
+    fn add_target(&mut self, hash: u32) {
+        let guess = self
+            .guesses
+            .get(&hash)
+            .and_then(|guesses| guesses.first())
+            .cloned();
+
+        self.targets.entry(hash).or_insert(guess);
+    }
+
+    fn print_targets(&self) {
+        let found = self
+            .targets
+            .values()
+            .filter(|guess| guess.is_some())
+            .count();
+
+        for (hash, guess) in &self.targets {
+            match guess {
+                Some(guess) => println!("{:#010X} {}", hash, guess),
+                None => println!("{:#010X} (unresolved)", hash),
+            }
+        }
+
+        println!("{}/{} targets found", found, self.targets.len());
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut writer = DictionaryWriter::create(path, OutputFormat::Csv, HashFormat::Upper)?;
+        let mut count = 0;
+
+        for (hash, guesses) in &self.guesses {
+            for guess in guesses {
+                writer.write(*hash, guess)?;
+                count += 1;
+            }
+        }
+
+        writer.finish()?;
+        println!("Saved {} strings to {}", count, path.display());
+
+        Ok(())
+    }
+}
+
+/// Default history file, kept in the user's home directory.
+pub fn default_history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".xxhash32_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_guesses_against_targets() {
+        let mut repl = Repl::new(Dictionary::default());
+
+        repl.eval(":target 0x48110BA3 9AD6310D").unwrap();
+        repl.eval("hello").unwrap();
+        repl.eval("WEP_PL1500_04_03").unwrap();
+
+        assert_eq!(
+            repl.targets,
+            BTreeMap::from([
+                (0x48110BA3, Some("WEP_PL1500_04_03".to_string())),
+                (0x9AD6310D, Some("hello".to_string())),
+            ])
+        );
+        assert!(repl.eval(":save").is_err());
+        assert!(repl.eval(":load").is_err());
+    }
+
+    #[test]
+    fn loads_and_saves_paths_with_spaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("saved names.csv");
+        let mut repl = Repl::new(Dictionary::default());
+
+        repl.eval(" hello").unwrap();
+        repl.eval(&format!(":save {}", path.display())).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("hash,value\n{:#010X}, hello\n", xxhash32_custom(b" hello"))
+        );
+
+        repl.eval(&format!(":load {}", path.display())).unwrap();
+        let entries = repl.dictionary.get(xxhash32_custom(b" hello"));
+        assert_eq!(entries[0].value, " hello");
+    }
+
+    #[test]
+    fn reads_targets_from_files_named_like_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("beef");
+        std::fs::write(&path, "0x48110BA3\nnot a hash\n0000ffff\n").unwrap();
+
+        let mut repl = Repl::new(Dictionary::default());
+        repl.add_targets(&path.to_string_lossy()).unwrap();
+        repl.add_targets("beef").unwrap();
+
+        let targets: Vec<u32> = repl.targets.keys().copied().collect();
+        assert_eq!(targets, [0xBEEF, 0xFFFF, 0x48110BA3]);
+
+        let path = dir.path().join("my targets.txt");
+        std::fs::write(&path, "9AD6310D\n").unwrap();
+        repl.eval(&format!(":target {}", path.display())).unwrap();
+        assert!(repl.targets.contains_key(&0x9AD6310D));
+    }
+}
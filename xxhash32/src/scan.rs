@@ -0,0 +1,180 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value};
+
+use crate::dictionary::{Dictionary, Entry};
+use crate::imhex;
+
+/// Bookmark colour used for matches, as ABGR.
+const BOOKMARK_COLOR: u32 = 0x8060C0FF;
+
+/// A little-endian u32 found in a binary that matches a dictionary hash.
+pub struct Match<'a> {
+    pub offset: usize,
+    pub hash: u32,
+    pub entries: Cow<'a, [Entry]>,
+}
+
+impl Match<'_> {
+    pub fn name(&self) -> &str {
+        self.entries
+            .first()
+            .map(|entry| entry.value.as_str())
+            .unwrap_or_default()
+    }
+
+    /// The ImHex enum this match belongs to, if it came from an extracted data file.
+    pub fn enum_name(&self) -> Option<&'static str> {
+        self.entries
+            .iter()
+            .find_map(|entry| imhex::enum_for_source(&entry.source))
+    }
+}
+
+/// Finds every little-endian u32 in `data` that's a known hash, checking every `alignment` bytes.
+pub fn scan<'a>(data: &[u8], dictionary: &'a Dictionary, alignment: usize) -> Vec<Match<'a>> {
+    let alignment = alignment.max(1);
+
+    (0..data.len().saturating_sub(3))
+        .step_by(alignment)
+        .filter_map(|offset| {
+            let hash = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+            let entries = dictionary.get(hash);
+
+            if entries.is_empty() {
+                None
+            } else {
+                Some(Match {
+                    offset,
+                    hash,
+                    entries,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Writes matches as an ImHex bookmark file (`.hexbm`), importable through File > Import > Bookmarks.
+pub fn to_bookmarks(matches: &[Match]) -> String {
+    let bookmarks: Map<String, Value> = matches
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let candidates: Vec<String> = m
+                .entries
+                .iter()
+                .map(|entry| match &entry.text {
+                    Some(text) => format!("{} [{}] {}", entry.value, entry.source, text),
+                    None => format!("{} [{}]", entry.value, entry.source),
+                })
+                .collect();
+
+            (
+                i.to_string(),
+                json!({
+                    "id": i + 1,
+                    "name": m.name(),
+                    "comment": format!("{:#010X}\n{}", m.hash, candidates.join("\n")),
+                    "color": BOOKMARK_COLOR,
+                    "locked": true,
+                    "region": {
+                        "address": m.offset,
+                        "size": 4,
+                    },
+                }),
+            )
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&json!({ "bookmarks": bookmarks })).unwrap()
+}
+
+/// Quotes a string for the ImHex pattern language, which only needs `\` and `"` escaped.
+fn pattern_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+
+    for c in text.chars() {
+        if c == '\\' || c == '"' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Writes matches as an ImHex pattern that places a typed u32 at every matched offset.
+///
+/// Matches from extracted data files use the matching `enum.pat` type, e.g. `gbfr::ItemID`, and
+/// everything else is a plain u32 commented with its name.
+pub fn to_hexpat(matches: &[Match], namespace: &str) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "// Generated by xxhash32 scan, do not edit.").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "#include <{}/enum.pat>", namespace).unwrap();
+    writeln!(output).unwrap();
+
+    for m in matches {
+        let field_type = match m.enum_name() {
+            Some(enum_name) => format!("{}::{}", namespace, enum_name),
+            None => "u32".to_string(),
+        };
+
+        writeln!(
+            output,
+            "{} hash_{:08X} @ {:#X} [[comment({})]];",
+            field_type,
+            m.offset,
+            m.offset,
+            pattern_string(m.name())
+        )
+        .unwrap();
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_hashes_at_alignment() {
+        let mut dictionary = Dictionary::default();
+        dictionary.insert(
+            0x48110BA3,
+            Entry {
+                value: "WEP_PL1500_04_03".to_string(),
+                source: "en/weapons.json".to_string(),
+                text: Some("Ascalon".to_string()),
+            },
+        );
+
+        let mut data = vec![0; 4];
+        data.extend(0x48110BA3u32.to_le_bytes());
+        data.extend([0; 1]);
+        data.extend(0x48110BA3u32.to_le_bytes());
+
+        let offsets: Vec<usize> = scan(&data, &dictionary, 4)
+            .iter()
+            .map(|m| m.offset)
+            .collect();
+        assert_eq!(offsets, vec![4]);
+
+        let matches = scan(&data, &dictionary, 1);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].offset, 9);
+        assert_eq!(matches[1].enum_name(), Some("WeaponID"));
+        assert!(to_hexpat(&matches, "gbfr")
+            .contains("gbfr::WeaponID hash_00000009 @ 0x9 [[comment(\"WEP_PL1500_04_03\")]];"));
+
+        assert_eq!(
+            pattern_string(r#"アスカロン "C:\x""#),
+            r#""アスカロン \"C:\\x\"""#
+        );
+    }
+}
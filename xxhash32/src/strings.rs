@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+/// Options controlling which byte runs are considered candidate strings.
+pub struct StringsOptions {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub utf16: bool,
+    pub identifiers_only: bool,
+}
+
+/// Extracts candidate strings from a binary blob, similar to `strings`.
+///
+/// UTF-8 runs may contain any printable character, UTF-16LE runs are limited to
+/// printable ASCII code units (like `strings -el`) since arbitrary code unit pairs
+/// otherwise decode to plausible-looking CJK noise.
+///
+/// Results are deduplicated and returned in the order they were first found.
+pub fn extract_strings(data: &[u8], options: &StringsOptions) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut results = Vec::new();
+
+    let mut push = |run: String| {
+        for candidate in split_candidates(&run, options) {
+            if seen.insert(candidate.clone()) {
+                results.push(candidate);
+            }
+        }
+    };
+
+    for run in utf8_runs(data) {
+        push(run);
+    }
+
+    if options.utf16 {
+        for alignment in 0..2 {
+            for run in utf16le_runs(&data[alignment.min(data.len())..]) {
+                push(run);
+            }
+        }
+    }
+
+    results
+}
+
+/// Applies the identifier filter and length limits to a raw run of printable text.
+fn split_candidates(run: &str, options: &StringsOptions) -> Vec<String> {
+    let pieces: Vec<&str> = if options.identifiers_only {
+        run.split(|c: char| !is_identifier_char(c)).collect()
+    } else {
+        vec![run]
+    };
+
+    pieces
+        .into_iter()
+        .filter(|piece| {
+            let length = piece.chars().count();
+            length >= options.min_length && length <= options.max_length
+        })
+        .map(|piece| piece.to_string())
+        .collect()
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_printable(c: char) -> bool {
+    !c.is_control() || c == '\t'
+}
+
+/// Returns the expected length of a UTF-8 sequence given its leading byte.
+fn utf8_sequence_length(byte: u8) -> usize {
+    match byte {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 0,
+    }
+}
+
+fn utf8_runs(data: &[u8]) -> Vec<String> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    let mut position = 0;
+
+    while position < data.len() {
+        let length = utf8_sequence_length(data[position]);
+        let decoded = data
+            .get(position..position + length.max(1))
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .and_then(|s| s.chars().next())
+            .filter(|c| length > 0 && is_printable(*c));
+
+        match decoded {
+            Some(c) => {
+                current.push(c);
+                position += length;
+            }
+            None => {
+                if !current.is_empty() {
+                    runs.push(std::mem::take(&mut current));
+                }
+                position += 1;
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        runs.push(current);
+    }
+
+    runs
+}
+
+fn utf16le_runs(data: &[u8]) -> Vec<String> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+
+    for unit in data.chunks_exact(2) {
+        let unit = u16::from_le_bytes([unit[0], unit[1]]);
+
+        match char::from_u32(unit as u32).filter(|c| c.is_ascii() && is_printable(*c)) {
+            Some(c) => current.push(c),
+            None => {
+                // Single characters are just the high/low byte of ASCII UTF-8 text.
+                if current.len() > 1 {
+                    runs.push(std::mem::take(&mut current));
+                } else {
+                    current.clear();
+                }
+            }
+        }
+    }
+
+    if current.len() > 1 {
+        runs.push(current);
+    }
+
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> StringsOptions {
+        StringsOptions {
+            min_length: 4,
+            max_length: 256,
+            utf16: true,
+            identifiers_only: false,
+        }
+    }
+
+    #[test]
+    fn extracts_utf8_and_utf16_strings() {
+        let mut data = b"\x00\x01WEP_PL1500_04_03\x00\xFFab\x00\xFF\xFF".to_vec();
+        data.extend("ITEM_01".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        data.extend(b"\x00\x00");
+
+        let strings = extract_strings(&data, &options());
+
+        assert_eq!(strings, vec!["WEP_PL1500_04_03", "ITEM_01"]);
+    }
+
+    #[test]
+    fn identifiers_only_splits_on_punctuation() {
+        let strings = extract_strings(
+            b"path/to/Pl0100 file",
+            &StringsOptions {
+                identifiers_only: true,
+                ..options()
+            },
+        );
+
+        assert_eq!(strings, vec!["path", "Pl0100", "file"]);
+    }
+}